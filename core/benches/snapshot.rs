@@ -0,0 +1,46 @@
+use std::rc::Rc;
+
+use core::{snapshot::LedgerSnapshot, testutils::persistent_data};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use soroban_env_host::{
+    storage::SnapshotSource,
+    xdr::{LedgerEntry, LedgerKey},
+};
+
+const ENTRIES: u32 = 50_000;
+
+fn data_entry(idx: u32) -> (LedgerKey, (LedgerEntry, Option<u32>)) {
+    let (key, entry) = persistent_data(idx, idx);
+    (key, (entry, Some(u32::MAX)))
+}
+
+fn snapshot_benches(c: &mut Criterion) {
+    let entries: Vec<_> = (0..ENTRIES).map(data_entry).collect();
+
+    c.bench_function("build 50k entry snapshot", |b| {
+        b.iter(|| {
+            let mut snapshot = LedgerSnapshot::default();
+            snapshot.extend_entries(entries.iter().cloned());
+            black_box(snapshot)
+        })
+    });
+
+    let mut snapshot = LedgerSnapshot::default();
+    snapshot.extend_entries(entries.iter().cloned());
+    let keys: Vec<Rc<LedgerKey>> = entries
+        .iter()
+        .step_by(50)
+        .map(|(key, _)| Rc::new(key.clone()))
+        .collect();
+
+    c.bench_function("get 1k keys from 50k entry snapshot", |b| {
+        b.iter(|| {
+            for key in &keys {
+                black_box(snapshot.get(key).unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, snapshot_benches);
+criterion_main!(benches);
@@ -0,0 +1,133 @@
+//! Serde helpers for the 32 byte addresses in the invocation params.
+//!
+//! Addresses are serialized as strkeys and can be deserialized from a strkey,
+//! a hex string or the raw 32 byte array.
+
+use serde::{de::Error, Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawAddress {
+    Str(String),
+    Bytes([u8; 32]),
+}
+
+fn from_hex(address: &str) -> Option<[u8; 32]> {
+    hex::decode(address).ok()?.try_into().ok()
+}
+
+fn deserialize_address<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    from_strkey: fn(&str) -> Option<[u8; 32]>,
+    expected: &str,
+) -> Result<[u8; 32], D::Error> {
+    match RawAddress::deserialize(deserializer)? {
+        RawAddress::Bytes(bytes) => Ok(bytes),
+        RawAddress::Str(address) => from_strkey(&address)
+            .or_else(|| from_hex(&address))
+            .ok_or_else(|| D::Error::custom(format!("invalid address {address:?}, expected {expected} or hex"))),
+    }
+}
+
+pub fn contract_strkey(contract: &[u8; 32]) -> String {
+    stellar_strkey::Contract(*contract).to_string()
+}
+
+pub fn account_strkey(account: &[u8; 32]) -> String {
+    stellar_strkey::ed25519::PublicKey(*account).to_string()
+}
+
+pub mod contract {
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(contract: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::contract_strkey(contract))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        super::deserialize_address(
+            deserializer,
+            |address| stellar_strkey::Contract::from_string(address).ok().map(|c| c.0),
+            "a contract strkey",
+        )
+    }
+}
+
+pub mod account {
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(account: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::account_strkey(account))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        super::deserialize_address(
+            deserializer,
+            |address| stellar_strkey::ed25519::PublicKey::from_string(address).ok().map(|a| a.0),
+            "an account strkey",
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Serialize;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    const CONTRACT: &str = "CADQOBYHA4DQOBYHA4DQOBYHA4DQOBYHA4DQOBYHA4DQOBYHA4DQP5KR";
+    const ACCOUNT: &str = "GAAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQDZ7H";
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Params {
+        #[serde(with = "contract")]
+        contract: [u8; 32],
+        #[serde(with = "account")]
+        source_account: [u8; 32],
+    }
+
+    fn params(contract: Value, source_account: Value) -> Result<Params, serde_json::Error> {
+        serde_json::from_value(json!({ "contract": contract, "source_account": source_account }))
+    }
+
+    #[test]
+    fn accepts_strkeys_hex_and_byte_arrays() {
+        let expected = Params {
+            contract: [7; 32],
+            source_account: [1; 32],
+        };
+
+        assert_eq!(params(json!(CONTRACT), json!(ACCOUNT)).unwrap(), expected);
+        assert_eq!(
+            params(json!(hex::encode([7; 32])), json!(hex::encode([1; 32]))).unwrap(),
+            expected
+        );
+        assert_eq!(
+            params(serde_json::to_value([7u8; 32]).unwrap(), serde_json::to_value([1u8; 32]).unwrap()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn rejects_the_wrong_strkey_kind_and_short_hex() {
+        let error = params(json!(CONTRACT), json!(CONTRACT)).unwrap_err().to_string();
+        assert!(error.contains("expected an account strkey or hex"), "{error}");
+
+        let error = params(json!(hex::encode([7; 16])), json!(ACCOUNT)).unwrap_err().to_string();
+        assert!(error.contains("expected a contract strkey or hex"), "{error}");
+    }
+
+    #[test]
+    fn serializes_strkeys() {
+        let params = Params {
+            contract: [7; 32],
+            source_account: [1; 32],
+        };
+
+        assert_eq!(
+            serde_json::to_value(params).unwrap(),
+            json!({ "contract": CONTRACT, "source_account": ACCOUNT })
+        );
+    }
+}
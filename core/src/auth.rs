@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use soroban_env_host::{
+    e2e_invoke::RecordingInvocationAuthMode,
+    xdr::{Limits, ReadXdr, SorobanAuthorizationEntry},
+};
+
+use crate::SoroflareError;
+
+/// How the host authorizes `require_auth` calls during the invocation.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMode {
+    /// Record the authorization entries the invocation needs (root auth only).
+    Record,
+    /// Enforce the supplied authorization entries.
+    Enforce,
+    /// Record the authorization entries, also allowing non-root auth.
+    RecordAllowNonroot,
+}
+
+/// An authorization entry, either as base64 XDR or in its JSON form.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum AuthEntryParam {
+    Xdr(String),
+    Json(SorobanAuthorizationEntry),
+}
+
+impl AuthEntryParam {
+    pub fn to_entry(&self) -> Result<SorobanAuthorizationEntry, SoroflareError> {
+        match self {
+            Self::Xdr(xdr) => SorobanAuthorizationEntry::from_xdr_base64(xdr, Limits::none())
+                .map_err(|e| SoroflareError::InvalidParams(format!("invalid auth entry: {e}"))),
+            Self::Json(entry) => Ok(entry.clone()),
+        }
+    }
+}
+
+/// How the host handles auth during the invocation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvocationAuth {
+    Record { allow_nonroot: bool },
+    Enforce(Vec<SorobanAuthorizationEntry>),
+}
+
+impl InvocationAuth {
+    pub fn recording_mode(&self) -> RecordingInvocationAuthMode {
+        match self {
+            Self::Record { allow_nonroot } => RecordingInvocationAuthMode::Recording(!allow_nonroot),
+            Self::Enforce(entries) => RecordingInvocationAuthMode::Enforcing(entries.clone()),
+        }
+    }
+}
+
+/// Resolves the auth mode and entries of the params. Without an explicit
+/// mode, supplying entries means enforcing them.
+pub fn auth_entries(
+    mode: Option<AuthMode>,
+    entries: Option<&[AuthEntryParam]>,
+) -> Result<InvocationAuth, SoroflareError> {
+    let mode = mode.unwrap_or(if entries.is_some() {
+        AuthMode::Enforce
+    } else {
+        AuthMode::Record
+    });
+
+    match (mode, entries) {
+        (AuthMode::Record, None) => Ok(InvocationAuth::Record { allow_nonroot: false }),
+        (AuthMode::RecordAllowNonroot, None) => Ok(InvocationAuth::Record { allow_nonroot: true }),
+        (AuthMode::Enforce, Some(entries)) => Ok(InvocationAuth::Enforce(
+            entries
+                .iter()
+                .map(AuthEntryParam::to_entry)
+                .collect::<Result<_, _>>()?,
+        )),
+        (AuthMode::Enforce, None) => Err(SoroflareError::InvalidParams(
+            "auth mode `enforce` requires `auth_entries`".into(),
+        )),
+        (AuthMode::Record | AuthMode::RecordAllowNonroot, Some(_)) => Err(
+            SoroflareError::InvalidParams("`auth_entries` can only be used with auth mode `enforce`".into()),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use soroban_env_host::xdr::{
+        Hash, InvokeContractArgs, ScAddress, ScSymbol, SorobanAuthorizedFunction, SorobanAuthorizedInvocation,
+        SorobanCredentials, WriteXdr,
+    };
+
+    use super::*;
+
+    fn entry() -> SorobanAuthorizationEntry {
+        SorobanAuthorizationEntry {
+            credentials: SorobanCredentials::SourceAccount,
+            root_invocation: SorobanAuthorizedInvocation {
+                function: SorobanAuthorizedFunction::ContractFn(InvokeContractArgs {
+                    contract_address: ScAddress::Contract(Hash([1; 32])),
+                    function_name: ScSymbol("hello".try_into().unwrap()),
+                    args: Default::default(),
+                }),
+                sub_invocations: Default::default(),
+            },
+        }
+    }
+
+    fn params() -> Vec<AuthEntryParam> {
+        vec![
+            AuthEntryParam::Xdr(entry().to_xdr_base64(Limits::none()).unwrap()),
+            AuthEntryParam::Json(entry()),
+        ]
+    }
+
+    #[test]
+    fn modes_and_entries_resolve_to_host_auth() {
+        let entries = params();
+        let record = |allow_nonroot| InvocationAuth::Record { allow_nonroot };
+        let enforce = InvocationAuth::Enforce(vec![entry(), entry()]);
+
+        assert_eq!(auth_entries(None, None).unwrap(), record(false));
+        assert_eq!(auth_entries(None, Some(&entries)).unwrap(), enforce);
+        assert_eq!(auth_entries(Some(AuthMode::Record), None).unwrap(), record(false));
+        assert_eq!(auth_entries(Some(AuthMode::RecordAllowNonroot), None).unwrap(), record(true));
+        assert_eq!(auth_entries(Some(AuthMode::Enforce), Some(&entries)).unwrap(), enforce);
+
+        assert!(auth_entries(Some(AuthMode::Enforce), None).is_err());
+        assert!(auth_entries(Some(AuthMode::Record), Some(&entries)).is_err());
+        assert!(auth_entries(Some(AuthMode::RecordAllowNonroot), Some(&entries)).is_err());
+        assert!(auth_entries(None, Some(&[AuthEntryParam::Xdr("AAAA".into())])).is_err());
+    }
+
+    #[test]
+    fn record_allow_nonroot_keeps_nonroot_auth_enabled() {
+        assert!(matches!(
+            auth_entries(Some(AuthMode::RecordAllowNonroot), None).unwrap().recording_mode(),
+            RecordingInvocationAuthMode::Recording(false)
+        ));
+        assert!(matches!(
+            auth_entries(None, None).unwrap().recording_mode(),
+            RecordingInvocationAuthMode::Recording(true)
+        ));
+    }
+}
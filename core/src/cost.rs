@@ -0,0 +1,133 @@
+//! Budget limits, and the CPU and memory an invocation consumed per cost type.
+
+use serde::{Deserialize, Serialize};
+use soroban_env_host::{budget::Budget, xdr::ContractCostType};
+use soroban_simulation::simulation::{SimulationAdjustmentConfig, SimulationAdjustmentFactor};
+
+use crate::{simulation::adjusted, SoroflareError};
+
+fn host_error(err: soroban_env_host::HostError) -> SoroflareError {
+    SoroflareError::Simulation(format!("failed to read budget: {err:?}"))
+}
+
+/// CPU instruction and memory limits for an invocation. Omitted limits are
+/// left to the network config, or to the host defaults without one.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct BudgetLimits {
+    pub cpu_insns: Option<u64>,
+    pub mem_bytes: Option<u64>,
+}
+
+fn stricter(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+impl BudgetLimits {
+    /// The stricter of both limits, per dimension.
+    pub fn stricter(self, other: Self) -> Self {
+        Self {
+            cpu_insns: stricter(self.cpu_insns, other.cpu_insns),
+            mem_bytes: stricter(self.mem_bytes, other.mem_bytes),
+        }
+    }
+
+    /// Lowers the limits of `budget` to these ones, never raising them.
+    pub(crate) fn apply(&self, budget: &Budget) -> Result<(), SoroflareError> {
+        let cpu_limit = budget.get_cpu_insns_remaining().map_err(host_error)?;
+        let mem_limit = budget.get_mem_bytes_remaining().map_err(host_error)?;
+
+        budget
+            .reset_limits(
+                self.cpu_insns.map_or(cpu_limit, |limit| limit.min(cpu_limit)),
+                self.mem_bytes.map_or(mem_limit, |limit| limit.min(mem_limit)),
+            )
+            .map_err(host_error)
+    }
+}
+
+/// How much budget a run consumed, and out of which limits.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct BudgetUsage {
+    pub cpu_insns: u64,
+    pub cpu_insns_limit: u64,
+    pub mem_bytes: u64,
+    pub mem_bytes_limit: u64,
+}
+
+impl BudgetUsage {
+    pub fn from_budget(budget: &Budget) -> Result<Self, SoroflareError> {
+        let cpu_insns = budget.get_cpu_insns_consumed().map_err(host_error)?;
+        let mem_bytes = budget.get_mem_bytes_consumed().map_err(host_error)?;
+
+        Ok(Self {
+            cpu_insns,
+            cpu_insns_limit: cpu_insns + budget.get_cpu_insns_remaining().map_err(host_error)?,
+            mem_bytes,
+            mem_bytes_limit: mem_bytes + budget.get_mem_bytes_remaining().map_err(host_error)?,
+        })
+    }
+}
+
+/// Scales a measured value by the adjustment's multiplicative factor only,
+/// the additive part is only added once, to the total.
+fn scale(value: u64, factor: &SimulationAdjustmentFactor) -> u64 {
+    value.max((value as f64 * factor.multiplicative_factor).floor() as u64)
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CostTypeUsage {
+    pub cost_type: ContractCostType,
+    /// Number of times the cost was charged.
+    pub iterations: u64,
+    pub cpu_insns: u64,
+    pub mem_bytes: u64,
+    /// `cpu_insns` scaled by the multiplicative part of the instructions
+    /// adjustment.
+    pub adjusted_cpu_insns: u64,
+}
+
+/// Consumed budget, totals and per cost type. The simulation only adjusts
+/// instructions, so memory is reported as measured.
+#[derive(Serialize, Clone, Debug)]
+pub struct CostBreakdown {
+    pub cpu_insns: u64,
+    pub mem_bytes: u64,
+    /// `cpu_insns` adjusted like the simulation adjusts the transaction's
+    /// instructions.
+    pub adjusted_cpu_insns: u64,
+    /// Cost types that were charged at least once.
+    pub by_cost_type: Vec<CostTypeUsage>,
+}
+
+impl CostBreakdown {
+    pub fn from_budget(budget: &Budget, adjustment: &SimulationAdjustmentConfig) -> Result<Self, SoroflareError> {
+        let factor = &adjustment.instructions;
+        let cpu_insns = budget.get_cpu_insns_consumed().map_err(host_error)?;
+
+        let mut by_cost_type = Vec::new();
+        for cost_type in ContractCostType::variants() {
+            let tracker = budget.get_tracker(cost_type).map_err(host_error)?;
+            if tracker.iterations == 0 {
+                continue;
+            }
+
+            by_cost_type.push(CostTypeUsage {
+                cost_type,
+                iterations: tracker.iterations,
+                cpu_insns: tracker.cpu,
+                mem_bytes: tracker.mem,
+                adjusted_cpu_insns: scale(tracker.cpu, factor),
+            });
+        }
+
+        Ok(Self {
+            cpu_insns,
+            mem_bytes: budget.get_mem_bytes_consumed().map_err(host_error)?,
+            adjusted_cpu_insns: adjusted(cpu_insns, factor),
+            by_cost_type,
+        })
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use soroban_env_host::{
+    xdr::{LedgerKey, ScErrorCode, ScErrorType},
+    HostError,
+};
+
+use crate::BudgetUsage;
+
+/// Errors that can occur while building or resolving a soroflare invocation.
+#[derive(Debug)]
+pub enum SoroflareError {
+    /// The invocation parameters can't be converted into a valid host function.
+    InvalidParams(String),
+    /// The simulation could not be set up or completed.
+    Simulation(String),
+    /// The host failed while executing the invocation.
+    Host(HostError),
+    /// The invocation required a ledger entry that is not in the snapshot.
+    MissingLedgerEntry(LedgerKey),
+    /// The invocation ran out of CPU or memory budget. `usage` tells how far
+    /// it got, when it could be measured.
+    BudgetExceeded {
+        error: HostError,
+        usage: Option<BudgetUsage>,
+    },
+}
+
+impl SoroflareError {
+    /// Classifies a host error, using `missing` as the key the snapshot failed
+    /// to provide (if any).
+    pub fn from_host_error(error: HostError, missing: Option<LedgerKey>) -> Self {
+        if error.error.is_type(ScErrorType::Budget) && error.error.is_code(ScErrorCode::ExceededLimit) {
+            return Self::BudgetExceeded { error, usage: None };
+        }
+
+        if error.error.is_type(ScErrorType::Storage) && error.error.is_code(ScErrorCode::MissingValue) {
+            if let Some(key) = missing {
+                return Self::MissingLedgerEntry(key);
+            }
+        }
+
+        Self::Host(error)
+    }
+}
+
+impl fmt::Display for SoroflareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidParams(reason) => write!(f, "invalid invocation params: {reason}"),
+            Self::Simulation(reason) => write!(f, "simulation failed: {reason}"),
+            Self::Host(error) => write!(f, "host error: {error:?}"),
+            Self::MissingLedgerEntry(key) => write!(f, "missing ledger entry: {key:?}"),
+            Self::BudgetExceeded { error, .. } => write!(f, "budget exceeded: {error:?}"),
+        }
+    }
+}
+
+impl std::error::Error for SoroflareError {}
+
+/// Reasons a WASM module is rejected on upload.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WasmValidationError {
+    TooLarge { size: usize, max_size: usize },
+    /// The module isn't a well-formed WASM binary.
+    Malformed { reason: String },
+    /// The module has no `contractenvmetav0` section.
+    MissingEnvMeta,
+    /// The module was built for an env interface this host doesn't support.
+    UnsupportedInterfaceVersion {
+        protocol_version: u32,
+        pre_release_version: u32,
+        supported_protocol_version: u32,
+        supported_pre_release_version: u32,
+    },
+    /// The host VM refused to load the module, e.g. because it uses floats or
+    /// other disallowed WASM features.
+    Rejected { reason: String },
+}
+
+impl fmt::Display for WasmValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLarge { size, max_size } => {
+                write!(f, "wasm is {size} bytes, the maximum is {max_size} bytes")
+            }
+            Self::Malformed { reason } => write!(f, "malformed wasm: {reason}"),
+            Self::MissingEnvMeta => write!(f, "wasm has no contractenvmetav0 section"),
+            Self::UnsupportedInterfaceVersion {
+                protocol_version,
+                pre_release_version,
+                supported_protocol_version,
+                supported_pre_release_version,
+            } => write!(
+                f,
+                "wasm targets protocol {protocol_version} (pre-release {pre_release_version}), \
+                 supported is up to protocol {supported_protocol_version} (pre-release {supported_pre_release_version})"
+            ),
+            Self::Rejected { reason } => write!(f, "wasm rejected by the host: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for WasmValidationError {}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use soroban_env_host::xdr::{
+    ContractEvent, ContractEventBody, ContractEventType, DiagnosticEvent, Hash, ScVal,
+};
+
+use crate::address;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Contract,
+    System,
+    Diagnostic,
+}
+
+impl From<ContractEventType> for EventKind {
+    fn from(event_type: ContractEventType) -> Self {
+        match event_type {
+            ContractEventType::Contract => Self::Contract,
+            ContractEventType::System => Self::System,
+            ContractEventType::Diagnostic => Self::Diagnostic,
+        }
+    }
+}
+
+/// An event with its emitting contract as strkey and its topics and data as
+/// JSON `ScVal`s.
+#[derive(Serialize, Clone, Debug)]
+pub struct DecodedEvent {
+    pub contract: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: EventKind,
+    pub topics: Vec<ScVal>,
+    pub data: ScVal,
+    /// Only set for diagnostic events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_successful_contract_call: Option<bool>,
+}
+
+impl From<&ContractEvent> for DecodedEvent {
+    fn from(event: &ContractEvent) -> Self {
+        let ContractEventBody::V0(body) = &event.body;
+
+        Self {
+            contract: event
+                .contract_id
+                .as_ref()
+                .map(|Hash(contract)| address::contract_strkey(contract)),
+            kind: event.type_.into(),
+            topics: body.topics.to_vec(),
+            data: body.data.clone(),
+            in_successful_contract_call: None,
+        }
+    }
+}
+
+impl From<&DiagnosticEvent> for DecodedEvent {
+    fn from(event: &DiagnosticEvent) -> Self {
+        Self {
+            in_successful_contract_call: Some(event.in_successful_contract_call),
+            ..(&event.event).into()
+        }
+    }
+}
+
+/// Selects which events are returned. Empty lists match everything.
+#[derive(Default, Clone, Debug)]
+pub struct EventFilter {
+    pub kinds: Vec<EventKind>,
+    /// Contract strkeys.
+    pub contracts: Vec<String>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &DecodedEvent) -> bool {
+        let kind_matches = self.kinds.is_empty() || self.kinds.contains(&event.kind);
+        let contract_matches = self.contracts.is_empty()
+            || event
+                .contract
+                .as_ref()
+                .is_some_and(|contract| self.contracts.contains(contract));
+
+        kind_matches && contract_matches
+    }
+
+    pub fn decode<'a, E>(&self, events: impl IntoIterator<Item = &'a E>) -> Vec<DecodedEvent>
+    where
+        E: 'a,
+        &'a E: Into<DecodedEvent>,
+    {
+        events
+            .into_iter()
+            .map(Into::into)
+            .filter(|event| self.matches(event))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use soroban_env_host::xdr::{ContractEventV0, ExtensionPoint, ScSymbol};
+
+    use super::*;
+
+    fn event(contract_id: Option<[u8; 32]>, type_: ContractEventType, topic: &str) -> ContractEvent {
+        ContractEvent {
+            ext: ExtensionPoint::V0,
+            contract_id: contract_id.map(Hash),
+            type_,
+            body: ContractEventBody::V0(ContractEventV0 {
+                topics: vec![ScVal::Symbol(ScSymbol(topic.try_into().unwrap()))].try_into().unwrap(),
+                data: ScVal::U32(1),
+            }),
+        }
+    }
+
+    fn topics(events: &[DecodedEvent]) -> Vec<ScVal> {
+        events.iter().map(|event| event.topics[0].clone()).collect()
+    }
+
+    #[test]
+    fn filters_by_kind_and_contract() {
+        let events = [
+            event(Some([1; 32]), ContractEventType::Contract, "transfer"),
+            event(Some([2; 32]), ContractEventType::Contract, "mint"),
+            event(None, ContractEventType::System, "upgrade"),
+        ];
+
+        let all = EventFilter::default().decode(&events);
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].contract.as_deref(), Some(address::contract_strkey(&[1; 32]).as_str()));
+        assert_eq!(all[0].kind, EventKind::Contract);
+        assert_eq!(all[0].data, ScVal::U32(1));
+        assert_eq!(all[0].in_successful_contract_call, None);
+        assert_eq!(all[2].contract, None);
+
+        let system = EventFilter {
+            kinds: vec![EventKind::System],
+            ..Default::default()
+        };
+        assert_eq!(topics(&system.decode(&events)), topics(&all[2..]));
+
+        let second_contract = EventFilter {
+            contracts: vec![address::contract_strkey(&[2; 32])],
+            ..Default::default()
+        };
+        assert_eq!(topics(&second_contract.decode(&events)), topics(&all[1..2]));
+
+        // An event without a contract never matches a contract filter.
+        let system_of_contract = EventFilter {
+            kinds: vec![EventKind::System],
+            contracts: vec![address::contract_strkey(&[1; 32])],
+        };
+        assert!(system_of_contract.decode(&events).is_empty());
+    }
+
+    #[test]
+    fn diagnostic_events_keep_the_call_outcome() {
+        let events = [DiagnosticEvent {
+            in_successful_contract_call: false,
+            event: event(Some([1; 32]), ContractEventType::Diagnostic, "fn_call"),
+        }];
+
+        let decoded = EventFilter::default().decode(&events);
+        assert_eq!(decoded[0].kind, EventKind::Diagnostic);
+        assert_eq!(decoded[0].in_successful_contract_call, Some(false));
+    }
+}
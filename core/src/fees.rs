@@ -0,0 +1,167 @@
+//! Resource fee of a simulated invocation, computed with the host's fee
+//! functions from the adjusted resources and the network config, and broken
+//! down into its parts.
+
+use serde::Serialize;
+use soroban_env_host::{
+    fees::{compute_rent_fee, compute_transaction_resource_fee, LedgerEntryRentChange, TransactionResources},
+    xdr::LedgerKey,
+};
+use soroban_simulation::{simulation::SimulationAdjustmentFactor, NetworkConfig};
+
+use crate::simulation::adjusted;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EntryRentFee {
+    pub key: LedgerKey,
+    pub fee: i64,
+}
+
+/// Fees in stroops.
+#[derive(Serialize, Clone, Debug)]
+pub struct FeeBreakdown {
+    /// Fee for instructions, reads, writes and transaction size, charged
+    /// whatever the outcome.
+    pub non_refundable_fee: i64,
+    /// Refundable fee for the emitted events and the return value. Rent is
+    /// also refundable and reported separately.
+    pub refundable_fee: i64,
+    /// Rent of the written and extended entries.
+    pub rent_fee: i64,
+    /// Rent of every written or extended entry, each priced on its own. The
+    /// write fee of the TTL entries is rounded up per entry, so the sum can
+    /// exceed `rent_fee` by a few stroops.
+    pub rent_fee_per_entry: Vec<EntryRentFee>,
+    /// What the refundable fee adjustment adds to `refundable_fee` and
+    /// `rent_fee`.
+    pub refundable_fee_adjustment: i64,
+    /// The resource fee of the transaction data, the sum of the non-refundable
+    /// fee, the refundable fee, the rent fee and the adjustment.
+    pub min_resource_fee: i64,
+}
+
+impl FeeBreakdown {
+    pub(crate) fn new(
+        config: &NetworkConfig,
+        refundable_adjustment: &SimulationAdjustmentFactor,
+        resources: &TransactionResources,
+        rent_changes: &[(LedgerKey, LedgerEntryRentChange)],
+        ledger_sequence: u32,
+    ) -> Self {
+        let (non_refundable_fee, refundable_fee) =
+            compute_transaction_resource_fee(resources, &config.fee_configuration);
+
+        let rent_fee = compute_rent_fee(
+            &rent_changes.iter().map(|(_, change)| change.clone()).collect::<Vec<_>>(),
+            &config.rent_fee_configuration,
+            ledger_sequence,
+        );
+        let rent_fee_per_entry = rent_changes
+            .iter()
+            .map(|(key, change)| EntryRentFee {
+                key: key.clone(),
+                fee: compute_rent_fee(
+                    std::slice::from_ref(change),
+                    &config.rent_fee_configuration,
+                    ledger_sequence,
+                ),
+            })
+            .collect();
+
+        let unadjusted = refundable_fee + rent_fee;
+        let adjusted_refundable_fee = adjusted(unadjusted as u64, refundable_adjustment) as i64;
+
+        Self {
+            non_refundable_fee,
+            refundable_fee,
+            rent_fee,
+            rent_fee_per_entry,
+            refundable_fee_adjustment: adjusted_refundable_fee - unadjusted,
+            min_resource_fee: non_refundable_fee + adjusted_refundable_fee,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use soroban_simulation::simulation::SimulationAdjustmentConfig;
+
+    use super::*;
+    use crate::{testutils::persistent_data, NetworkPreset};
+
+    fn data_key(key: u32) -> LedgerKey {
+        persistent_data(key, 0).0
+    }
+
+    fn resources() -> TransactionResources {
+        TransactionResources {
+            instructions: 1_000_000,
+            read_entries: 3,
+            write_entries: 1,
+            read_bytes: 2_000,
+            write_bytes: 200,
+            contract_events_size_bytes: 300,
+            transaction_size_bytes: 1_500,
+        }
+    }
+
+    #[test]
+    fn min_resource_fee_sums_the_parts() {
+        let config = NetworkPreset::Testnet.config(20).unwrap();
+        let adjustment = SimulationAdjustmentConfig::default_adjustment();
+        let rent_changes = [(
+            data_key(1),
+            LedgerEntryRentChange {
+                is_persistent: true,
+                old_size_bytes: 0,
+                new_size_bytes: 200,
+                old_live_until_ledger: 0,
+                new_live_until_ledger: 100 + config.min_persistent_entry_ttl - 1,
+            },
+        )];
+
+        let fees = FeeBreakdown::new(&config, &adjustment.refundable_fee, &resources(), &rent_changes, 100);
+
+        assert!(fees.non_refundable_fee > 0);
+        assert!(fees.refundable_fee > 0);
+        assert!(fees.rent_fee > 0);
+        assert_eq!(fees.rent_fee_per_entry[0].fee, fees.rent_fee);
+        assert_eq!(
+            fees.refundable_fee_adjustment,
+            ((fees.refundable_fee + fees.rent_fee) as f64 * 1.15).floor() as i64 - fees.refundable_fee - fees.rent_fee
+        );
+        assert_eq!(
+            fees.min_resource_fee,
+            fees.non_refundable_fee + fees.refundable_fee + fees.rent_fee + fees.refundable_fee_adjustment
+        );
+    }
+
+    #[test]
+    fn ttl_extensions_pay_rent() {
+        let config = NetworkPreset::Testnet.config(20).unwrap();
+        let adjustment = SimulationAdjustmentConfig::default_adjustment();
+        let extension = LedgerEntryRentChange {
+            is_persistent: true,
+            old_size_bytes: 200,
+            new_size_bytes: 200,
+            old_live_until_ledger: 1_000,
+            new_live_until_ledger: 100_000,
+        };
+        let unchanged = LedgerEntryRentChange {
+            new_live_until_ledger: 1_000,
+            ..extension.clone()
+        };
+
+        let fees = FeeBreakdown::new(
+            &config,
+            &adjustment.refundable_fee,
+            &resources(),
+            &[(data_key(1), extension), (data_key(2), unchanged)],
+            500,
+        );
+
+        assert!(fees.rent_fee > 0);
+        assert!(fees.rent_fee_per_entry[0].fee > 0);
+        assert_eq!(fees.rent_fee_per_entry[1].fee, 0);
+    }
+}
@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use soroban_env_host::xdr::{
+    AccountId, Asset, ContractExecutable, ContractIdPreimage, ContractIdPreimageFromAddress,
+    CreateContractArgs, Hash, HostFunction, InvokeContractArgs, PublicKey, ScAddress, ScSymbol,
+    ScVal, StringM, Uint256,
+};
+
+use crate::{address, SoroflareError};
+
+/// The host function to simulate, tagged by `type`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HostFunctionParams {
+    InvokeContract {
+        #[serde(with = "address::contract")]
+        contract: [u8; 32],
+        fname: String,
+        #[serde(default)]
+        args: Vec<ScVal>,
+    },
+    /// Uploads the hex encoded `wasm`.
+    UploadContractWasm { wasm: String },
+    /// Deploys the uploaded WASM with hex hash `wasm_hash`. The source account
+    /// is the deployer, and `salt` (hex) defaults to zero.
+    CreateContract {
+        wasm_hash: String,
+        salt: Option<String>,
+    },
+    /// Deploys the Stellar asset contract for `asset`.
+    CreateAssetContract { asset: Asset },
+}
+
+fn decode_hash(name: &str, value: &str) -> Result<[u8; 32], SoroflareError> {
+    hex::decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| SoroflareError::InvalidParams(format!("invalid {name} {value:?}, expected 32 hex encoded bytes")))
+}
+
+pub fn invoke_contract(
+    contract: [u8; 32],
+    fname: &str,
+    args: &[ScVal],
+) -> Result<HostFunction, SoroflareError> {
+    let function_name = <_ as TryInto<StringM<32>>>::try_into(fname).map_err(|_| {
+        SoroflareError::InvalidParams(format!("invalid function name {fname:?}"))
+    })?;
+
+    Ok(HostFunction::InvokeContract(InvokeContractArgs {
+        contract_address: ScAddress::Contract(Hash(contract)),
+        function_name: ScSymbol(function_name),
+        args: args
+            .to_vec()
+            .try_into()
+            .map_err(|_| SoroflareError::InvalidParams("too many arguments".into()))?,
+    }))
+}
+
+impl HostFunctionParams {
+    pub fn host_function(&self, source_account: [u8; 32]) -> Result<HostFunction, SoroflareError> {
+        match self {
+            Self::InvokeContract { contract, fname, args } => invoke_contract(*contract, fname, args),
+            Self::UploadContractWasm { wasm } => {
+                let wasm = hex::decode(wasm)
+                    .map_err(|e| SoroflareError::InvalidParams(format!("invalid wasm hex: {e}")))?;
+
+                Ok(HostFunction::UploadContractWasm(wasm.try_into().map_err(|_| {
+                    SoroflareError::InvalidParams("wasm is too large".into())
+                })?))
+            }
+            Self::CreateContract { wasm_hash, salt } => {
+                let salt = match salt {
+                    Some(salt) => decode_hash("salt", salt)?,
+                    None => [0; 32],
+                };
+                let deployer = ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(source_account))));
+
+                Ok(HostFunction::CreateContract(CreateContractArgs {
+                    contract_id_preimage: ContractIdPreimage::Address(ContractIdPreimageFromAddress {
+                        address: deployer,
+                        salt: Uint256(salt),
+                    }),
+                    executable: ContractExecutable::Wasm(Hash(decode_hash("wasm hash", wasm_hash)?)),
+                }))
+            }
+            Self::CreateAssetContract { asset } => Ok(HostFunction::CreateContract(CreateContractArgs {
+                contract_id_preimage: ContractIdPreimage::Asset(asset.clone()),
+                executable: ContractExecutable::StellarAsset,
+            })),
+        }
+    }
+
+    /// Hash of the WASM the host function needs to find in the ledger.
+    pub fn wasm_hash(&self) -> Option<[u8; 32]> {
+        match self {
+            Self::CreateContract { wasm_hash, .. } => decode_hash("wasm hash", wasm_hash).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// The contract id (as strkey) or WASM hash (as hex) produced by a deployment
+/// host function, read from its return value.
+pub fn created_id(host_fn: &HostFunction, result: &ScVal) -> Option<String> {
+    match (host_fn, result) {
+        (HostFunction::UploadContractWasm(_), ScVal::Bytes(hash)) => Some(hex::encode(hash.0.as_slice())),
+        (HostFunction::CreateContract(_), ScVal::Address(ScAddress::Contract(Hash(contract)))) => {
+            Some(address::contract_strkey(contract))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sha2::{Digest, Sha256};
+    use soroban_env_host::xdr::{HashIdPreimage, HashIdPreimageContractId, Limits, WriteXdr};
+
+    use super::*;
+    use crate::{
+        snapshot::hashed_network_id,
+        testutils::{host_function_params, NETWORK},
+        SoroflareInvocation,
+    };
+
+    const WASM: &[u8] = include_bytes!("../../soroflare-wrangler/game_engine.wasm");
+
+    #[test]
+    fn uploads_and_deploys_a_module() {
+        let wasm_hash: [u8; 32] = Sha256::digest(WASM).into();
+
+        let upload = SoroflareInvocation::new(host_function_params(
+            HostFunctionParams::UploadContractWasm { wasm: hex::encode(WASM) },
+            None,
+        ))
+        .unwrap();
+        let uploaded = upload.resolve().unwrap();
+        assert_eq!(upload.created_id(&uploaded), Some(hex::encode(wasm_hash)));
+
+        let salt = [1; 32];
+        let create = HostFunctionParams::CreateContract {
+            wasm_hash: hex::encode(wasm_hash),
+            salt: Some(hex::encode(salt)),
+        };
+        assert_eq!(create.wasm_hash(), Some(wasm_hash));
+
+        let deploy = SoroflareInvocation::new_on(host_function_params(create, None), &upload.snapshot_after(&uploaded))
+            .unwrap();
+        let deployed = deploy.resolve().unwrap();
+
+        let preimage = HashIdPreimage::ContractId(HashIdPreimageContractId {
+            network_id: Hash(hashed_network_id(NETWORK)),
+            contract_id_preimage: ContractIdPreimage::Address(ContractIdPreimageFromAddress {
+                address: ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256([0; 32])))),
+                salt: Uint256(salt),
+            }),
+        });
+        let contract: [u8; 32] = Sha256::digest(preimage.to_xdr(Limits::none()).unwrap()).into();
+        assert_eq!(deploy.created_id(&deployed), Some(address::contract_strkey(&contract)));
+    }
+
+    #[test]
+    fn create_contract_decodes_salt_and_hash() {
+        let create = |wasm_hash: &str, salt: Option<&str>| {
+            HostFunctionParams::CreateContract {
+                wasm_hash: wasm_hash.into(),
+                salt: salt.map(Into::into),
+            }
+            .host_function([0; 32])
+        };
+
+        let Ok(HostFunction::CreateContract(args)) = create(&hex::encode([2; 32]), None) else {
+            panic!("not a create contract host function");
+        };
+        assert_eq!(args.executable, ContractExecutable::Wasm(Hash([2; 32])));
+        let ContractIdPreimage::Address(preimage) = args.contract_id_preimage else {
+            panic!("not deployed from an address");
+        };
+        assert_eq!(preimage.salt, Uint256([0; 32]));
+
+        assert!(matches!(
+            create(&hex::encode([2; 32]), Some("0102")),
+            Err(SoroflareError::InvalidParams(error)) if error.contains("invalid salt")
+        ));
+        assert!(matches!(
+            create("not hex", None),
+            Err(SoroflareError::InvalidParams(error)) if error.contains("invalid wasm hash")
+        ));
+    }
+}
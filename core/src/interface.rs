@@ -0,0 +1,93 @@
+//! Decoded interface of a contract WASM: its spec, contract meta and env meta.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use soroban_env_host::xdr::{
+    ScEnvMetaEntry, ScMetaEntry, ScSpecEntry, ScSpecFunctionV0, ScSpecUdtEnumV0,
+    ScSpecUdtErrorEnumV0, ScSpecUdtStructV0, ScSpecUdtUnionV0,
+};
+
+use crate::{
+    spec::{read_xdr_stream, SPEC_SECTION},
+    wasm, SoroflareError,
+};
+
+pub const META_SECTION: &str = "contractmetav0";
+pub const ENV_META_SECTION: &str = "contractenvmetav0";
+
+/// The env interface version a contract was built against.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnvMeta {
+    pub interface_version: u64,
+    /// High 32 bits of the interface version.
+    pub protocol_version: u32,
+    /// Low 32 bits of the interface version, zero for release builds.
+    pub pre_release_version: u32,
+}
+
+impl EnvMeta {
+    /// Reads the env meta of a WASM, `None` if it has no env meta section.
+    pub fn from_wasm(wasm: &[u8]) -> Result<Option<Self>, SoroflareError> {
+        let Some(section) = wasm::custom_section(wasm, ENV_META_SECTION)? else {
+            return Ok(None);
+        };
+
+        Ok(read_xdr_stream::<ScEnvMetaEntry>(section)?
+            .into_iter()
+            .map(|ScEnvMetaEntry::ScEnvMetaKindInterfaceVersion(interface_version)| Self {
+                interface_version,
+                protocol_version: (interface_version >> 32) as u32,
+                pre_release_version: interface_version as u32,
+            })
+            .next())
+    }
+}
+
+/// Spec entries grouped by kind, along with the contract and env meta.
+///
+/// The spec format of this protocol version has no event entries, so events
+/// aren't part of the interface.
+#[derive(Serialize, Default, Debug)]
+pub struct ContractInterface {
+    pub functions: Vec<ScSpecFunctionV0>,
+    pub structs: Vec<ScSpecUdtStructV0>,
+    pub unions: Vec<ScSpecUdtUnionV0>,
+    pub enums: Vec<ScSpecUdtEnumV0>,
+    pub error_enums: Vec<ScSpecUdtErrorEnumV0>,
+    /// Key-value pairs of the contract meta, such as `rsver` and `rssdkver`.
+    pub meta: BTreeMap<String, String>,
+    pub env_meta: Option<EnvMeta>,
+}
+
+impl ContractInterface {
+    /// Decodes the interface of a WASM. Missing sections are left empty.
+    pub fn from_wasm(wasm: &[u8]) -> Result<Self, SoroflareError> {
+        let mut interface = Self {
+            env_meta: EnvMeta::from_wasm(wasm)?,
+            ..Default::default()
+        };
+
+        if let Some(section) = wasm::custom_section(wasm, SPEC_SECTION)? {
+            for entry in read_xdr_stream::<ScSpecEntry>(section)? {
+                match entry {
+                    ScSpecEntry::FunctionV0(function) => interface.functions.push(function),
+                    ScSpecEntry::UdtStructV0(udt) => interface.structs.push(udt),
+                    ScSpecEntry::UdtUnionV0(udt) => interface.unions.push(udt),
+                    ScSpecEntry::UdtEnumV0(udt) => interface.enums.push(udt),
+                    ScSpecEntry::UdtErrorEnumV0(udt) => interface.error_enums.push(udt),
+                }
+            }
+        }
+
+        if let Some(section) = wasm::custom_section(wasm, META_SECTION)? {
+            for ScMetaEntry::ScMetaV0(meta) in read_xdr_stream::<ScMetaEntry>(section)? {
+                interface
+                    .meta
+                    .insert(meta.key.to_utf8_string_lossy(), meta.val.to_utf8_string_lossy());
+            }
+        }
+
+        Ok(interface)
+    }
+}
@@ -1,22 +1,127 @@
 use std::rc::Rc;
 use serde::{Deserialize, Serialize};
-use snapshot::{hashed_network_id, LedgerSnapshot};
-use soroban_env_host::xdr::{AccountId, Hash, HostFunction, InvokeContractArgs, LedgerEntry, LedgerKey, PublicKey, ScAddress, ScSymbol, ScVal, ScVec, StringM, Uint256};
-use soroban_simulation::{simulation::{InvokeHostFunctionSimulationResult, SimulationAdjustmentConfig}, NetworkConfig};
+use snapshot::{hashed_network_id, LedgerSnapshot, SnapshotFile};
+use soroban_env_host::{budget::Budget, e2e_invoke, HostError};
+use soroban_env_host::xdr::{AccountId, Hash, HostFunction, InvokeContractArgs, LedgerEntry, LedgerKey, PublicKey, ScAddress, ScVal, Uint256};
+use soroban_simulation::{simulation::{RestoreOpSimulationResult, SimulationAdjustmentConfig}, NetworkConfig};
 
-mod snapshot;
+pub mod address;
+mod auth;
+mod cost;
+mod error;
+mod events;
+mod fees;
+mod host_function;
+pub mod interface;
+pub mod native;
+mod network;
+mod scenario;
+mod simulation;
+pub mod snapshot;
+pub mod spec;
+#[cfg(any(test, feature = "testutils"))]
+pub mod testutils;
+pub mod wasm;
+
+pub use auth::{AuthEntryParam, AuthMode};
+pub use cost::{BudgetLimits, BudgetUsage, CostBreakdown, CostTypeUsage};
+pub use error::{SoroflareError, WasmValidationError};
+pub use events::{DecodedEvent, EventFilter, EventKind};
+pub use fees::{EntryRentFee, FeeBreakdown};
+pub use host_function::HostFunctionParams;
+pub use network::NetworkPreset;
+pub use scenario::{ScenarioStep, SoroflareScenario};
+pub use simulation::{LedgerEntryDiff, SimulationResult, TtlChange};
+
+/// Protocol version used when the caller doesn't ask for a specific one.
+pub const DEFAULT_PROTOCOL_VERSION: u32 = 20;
+/// Base reserve of the public networks, in stroops.
+pub const DEFAULT_BASE_RESERVE: u32 = 5_000_000;
+pub const DEFAULT_MIN_PERSISTENT_ENTRY_TTL: u32 = 2_073_600;
+pub const DEFAULT_MIN_TEMP_ENTRY_TTL: u32 = 17_280;
+pub const DEFAULT_MAX_ENTRY_TTL: u32 = 3_110_400;
+
+/// Ledger header overrides. Omitted fields keep the value of the snapshot
+/// file if one is given, else fall back to the TTL limits of the supplied
+/// `network_config`, then to the public network defaults.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct LedgerInfoParams {
+    pub protocol_version: Option<u32>,
+    pub timestamp: Option<u64>,
+    pub base_reserve: Option<u32>,
+    pub min_persistent_entry_ttl: Option<u32>,
+    pub min_temp_entry_ttl: Option<u32>,
+    pub max_entry_ttl: Option<u32>,
+}
+
+impl LedgerInfoParams {
+    fn apply(&self, snapshot: &mut LedgerSnapshot) {
+        snapshot.protocol_version = self.protocol_version.unwrap_or(snapshot.protocol_version);
+        snapshot.timestamp = self.timestamp.unwrap_or(snapshot.timestamp);
+        snapshot.base_reserve = self.base_reserve.unwrap_or(snapshot.base_reserve);
+        snapshot.min_persistent_entry_ttl = self
+            .min_persistent_entry_ttl
+            .unwrap_or(snapshot.min_persistent_entry_ttl);
+        snapshot.min_temp_entry_ttl = self.min_temp_entry_ttl.unwrap_or(snapshot.min_temp_entry_ttl);
+        snapshot.max_entry_ttl = self.max_entry_ttl.unwrap_or(snapshot.max_entry_ttl);
+    }
+}
+
+/// Seed for the host PRNG, given either as a hex string or as a byte array.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum PrngSeed {
+    Hex(String),
+    Bytes([u8; 32]),
+}
+
+impl PrngSeed {
+    pub fn to_bytes(&self) -> Result<[u8; 32], SoroflareError> {
+        match self {
+            Self::Hex(seed) => hex::decode(seed)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| SoroflareError::InvalidParams(format!("invalid prng seed {seed:?}, expected 32 hex encoded bytes"))),
+            Self::Bytes(bytes) => Ok(*bytes),
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct SoroflareInvocationParams {
+    #[serde(default)]
     fname: String,
+    #[serde(with = "address::contract", default)]
     contract: [u8; 32],
+    #[serde(default)]
     args: Vec<ScVal>,
+    /// Plain JSON arguments keyed by parameter name, converted to `ScVal`s
+    /// using the spec of the invoked contract. Replaces `args`.
+    named_args: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Host function to run instead of invoking `fname` on `contract`.
+    host_function: Option<HostFunctionParams>,
+    #[serde(with = "address::account")]
     source_account: [u8; 32],
-    ledger_sequence: u32,
+    ledger_sequence: Option<u32>,
+    #[serde(default)]
     ledger_entries: Vec<(LedgerKey, (LedgerEntry, Option<u32>))>,
+    /// A whole snapshot file used as the ledger source. `ledger_entries` are
+    /// layered over its entries.
+    snapshot: Option<SnapshotFile>,
+    /// Id of a snapshot stored on soroflare, used like `snapshot`.
+    snapshot_id: Option<String>,
     network: Option<String>,
+    /// A full network config, or a [`NetworkPreset`] name optionally with
+    /// field overrides.
+    #[serde(deserialize_with = "network::deserialize_config", default)]
     network_config: Option<NetworkConfig>,
     adjustment_config: Option<SimulationAdjustmentConfig>,
+    /// Lowers the CPU and memory limits of the network config.
+    budget_limits: Option<BudgetLimits>,
+    ledger_info: Option<LedgerInfoParams>,
+    auth_mode: Option<AuthMode>,
+    auth_entries: Option<Vec<AuthEntryParam>>,
+    prng_seed: Option<PrngSeed>,
 }
 
 impl SoroflareInvocationParams {
@@ -35,15 +140,86 @@ impl SoroflareInvocationParams {
             fname,
             contract,
             args,
+            named_args: None,
+            host_function: None,
             source_account,
-            ledger_sequence,
+            ledger_sequence: Some(ledger_sequence),
             ledger_entries,
+            snapshot: None,
+            snapshot_id: None,
             network,
             network_config,
             adjustment_config,
+            budget_limits: None,
+            ledger_info: None,
+            auth_mode: None,
+            auth_entries: None,
+            prng_seed: None,
         }
     }
+
+    pub fn with_host_function(mut self, host_function: HostFunctionParams) -> Self {
+        self.host_function = Some(host_function);
+        self
+    }
+
+    pub fn with_named_args(mut self, named_args: serde_json::Map<String, serde_json::Value>) -> Self {
+        self.named_args = Some(named_args);
+        self
+    }
+
+    pub fn with_snapshot(mut self, snapshot: SnapshotFile) -> Self {
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    pub fn snapshot_id(&self) -> Option<&str> {
+        self.snapshot_id.as_deref()
+    }
+
+    /// Replaces the stored snapshot reference with the snapshot it points to.
+    pub fn resolve_snapshot_id(&mut self, snapshot: LedgerSnapshot) -> Result<(), SoroflareError> {
+        if self.snapshot.is_some() {
+            return Err(SoroflareError::InvalidParams(
+                "`snapshot` and `snapshot_id` can't be used together".into(),
+            ));
+        }
+
+        self.snapshot_id = None;
+        self.snapshot = Some(SnapshotFile::Ledger(snapshot));
+        Ok(())
+    }
+
+    pub fn with_ledger_info(mut self, ledger_info: LedgerInfoParams) -> Self {
+        self.ledger_info = Some(ledger_info);
+        self
+    }
     
+    pub fn with_budget_limits(mut self, limits: BudgetLimits) -> Self {
+        self.budget_limits = Some(limits);
+        self
+    }
+
+    /// Caps the requested budget limits at `ceiling`, requests can only ask
+    /// for lower limits.
+    pub fn cap_budget_limits(&mut self, ceiling: BudgetLimits) {
+        self.budget_limits = Some(self.budget_limits.unwrap_or_default().stricter(ceiling));
+    }
+
+    pub fn with_auth(mut self, mode: AuthMode, entries: Option<Vec<AuthEntryParam>>) -> Self {
+        self.auth_mode = Some(mode);
+        self.auth_entries = entries;
+        self
+    }
+
+    pub fn prng_seed(&self) -> Option<&PrngSeed> {
+        self.prng_seed.as_ref()
+    }
+
+    pub fn set_prng_seed(&mut self, seed: PrngSeed) {
+        self.prng_seed = Some(seed)
+    }
+
     pub fn entries(&self) -> Vec<(LedgerKey, (LedgerEntry, Option<u32>))> {
         self.ledger_entries.clone()
     }
@@ -52,32 +228,96 @@ impl SoroflareInvocationParams {
         self.ledger_entries = entries
     }
 
-    pub fn host_function(&self) -> HostFunction {
-        let mut complete_args = vec![];
-        complete_args.extend_from_slice(self.args.as_slice());
+    pub fn host_function(&self) -> Result<HostFunction, SoroflareError> {
+        match &self.host_function {
+            Some(host_function) => host_function.host_function(self.source_account),
+            None => host_function::invoke_contract(self.contract, &self.fname, &self.args),
+        }
+    }
 
-        let invoke_args = InvokeContractArgs {
-            contract_address: ScAddress::Contract(Hash(self.contract.clone())),
-            function_name: ScSymbol(<_ as TryInto<StringM<32>>>::try_into(&self.fname).unwrap()),
-            args: complete_args
-                .try_into()
-                .unwrap(),
+    /// Resolves `named_args` against the spec of the invoked contract, found
+    /// in `snapshot`.
+    fn host_function_on(&self, snapshot: &LedgerSnapshot) -> Result<HostFunction, SoroflareError> {
+        let Some(named_args) = &self.named_args else {
+            return self.host_function();
         };
 
-        HostFunction::InvokeContract(invoke_args)
+        let (contract, fname) = match &self.host_function {
+            None => (self.contract, self.fname.as_str()),
+            Some(HostFunctionParams::InvokeContract { contract, fname, args }) => {
+                if !args.is_empty() {
+                    return Err(SoroflareError::InvalidParams(
+                        "`args` and `named_args` can't be used together".into(),
+                    ));
+                }
+                (*contract, fname.as_str())
+            }
+            Some(_) => {
+                return Err(SoroflareError::InvalidParams(
+                    "`named_args` can only be used to invoke a contract".into(),
+                ))
+            }
+        };
+        if !self.args.is_empty() {
+            return Err(SoroflareError::InvalidParams(
+                "`args` and `named_args` can't be used together".into(),
+            ));
+        }
+
+        let wasm = snapshot.contract_wasm(&contract).ok_or_else(|| {
+            SoroflareError::InvalidParams(format!(
+                "no wasm found for contract {}, can't resolve `named_args`",
+                address::contract_strkey(&contract)
+            ))
+        })?;
+        let args = spec::ContractSpec::from_wasm(&wasm)?.args_from_json(fname, named_args)?;
+
+        host_function::invoke_contract(contract, fname, &args)
+    }
+
+    /// Hash of a WASM the host function itself references, on top of the ones
+    /// referenced by contract instances in the ledger entries.
+    pub fn host_function_wasm_hash(&self) -> Option<[u8; 32]> {
+        self.host_function.as_ref().and_then(HostFunctionParams::wasm_hash)
     }
 
     pub fn snapshot(&self) -> LedgerSnapshot {
-        let network = &self.network.clone().unwrap_or("Soroflare Stellar Network ; March 2024".into());
-        let ledger_entries = self.ledger_entries.iter().map(|entry| {
-            (Box::new(entry.clone().0), (Box::new(entry.clone().1.0), entry.1.1))
-        }).collect();
+        let mut snapshot = match &self.snapshot {
+            Some(file) => file.clone().into_snapshot(),
+            None => self.default_snapshot(),
+        };
+        self.apply_header(&mut snapshot);
+        snapshot.extend_entries(self.ledger_entries.iter().cloned());
+
+        snapshot
+    }
+
+    /// Overrides the header of `snapshot` with the network, sequence and
+    /// ledger info given in the params.
+    fn apply_header(&self, snapshot: &mut LedgerSnapshot) {
+        if let Some(network) = &self.network {
+            snapshot.network_id = hashed_network_id(network);
+        }
+        if let Some(ledger_sequence) = self.ledger_sequence {
+            snapshot.sequence_number = ledger_sequence;
+        }
+        if let Some(ledger_info) = &self.ledger_info {
+            ledger_info.apply(snapshot);
+        }
+    }
+
+    fn default_snapshot(&self) -> LedgerSnapshot {
+        let network_config = self.network_config.as_ref();
 
         LedgerSnapshot {
-            network_id: hashed_network_id(network),
-            sequence_number: self.ledger_sequence,
-            ledger_entries,
-            protocol_version: 20,
+            protocol_version: DEFAULT_PROTOCOL_VERSION,
+            network_id: hashed_network_id("Soroflare Stellar Network ; March 2024"),
+            base_reserve: DEFAULT_BASE_RESERVE,
+            min_persistent_entry_ttl: network_config
+                .map_or(DEFAULT_MIN_PERSISTENT_ENTRY_TTL, |c| c.min_persistent_entry_ttl),
+            min_temp_entry_ttl: network_config
+                .map_or(DEFAULT_MIN_TEMP_ENTRY_TTL, |c| c.min_temp_entry_ttl),
+            max_entry_ttl: network_config.map_or(DEFAULT_MAX_ENTRY_TTL, |c| c.max_entry_ttl),
             ..Default::default()
         }
     }
@@ -87,49 +327,344 @@ impl SoroflareInvocationParams {
 pub struct ConfigSetup {
     network_config: Option<NetworkConfig>,
     adjustment_config: SimulationAdjustmentConfig,
+    budget_limits: BudgetLimits,
 }
 
 pub struct SoroflareInvocation {
     config_setup: ConfigSetup,
     host_fn: HostFunction,
+    auth: auth::InvocationAuth,
+    prng_seed: [u8; 32],
     source_account: AccountId,
     snapshot: Rc<LedgerSnapshot>
 }
 
-// todo: implement restore preamble
-
 impl SoroflareInvocation {
-    pub fn new(params: SoroflareInvocationParams) -> Self {
-        let host_fn = params.host_function();
-        let snapshot = Rc::new(params.snapshot());
+    pub fn new(params: SoroflareInvocationParams) -> Result<Self, SoroflareError> {
+        let snapshot = params.snapshot();
+        Self::with_snapshot(params, snapshot)
+    }
+
+    /// Builds the invocation on top of the entries of `base`, with the entries
+    /// of the params' snapshot file, then their own ledger entries, layered
+    /// over them.
+    ///
+    /// The ledger header is the one of the snapshot file if the params have
+    /// one, else the one of `base`. The params' header fields override it.
+    pub fn new_on(params: SoroflareInvocationParams, base: &LedgerSnapshot) -> Result<Self, SoroflareError> {
+        let own = params.snapshot();
+        let mut snapshot = if params.snapshot.is_some() {
+            base.with_header_of(&own)
+        } else {
+            let mut snapshot = base.with_header_of(base);
+            params.apply_header(&mut snapshot);
+            snapshot
+        };
+        snapshot.extend_entries(own.entries());
+        Self::with_snapshot(params, snapshot)
+    }
+
+    fn with_snapshot(params: SoroflareInvocationParams, snapshot: LedgerSnapshot) -> Result<Self, SoroflareError> {
+        let host_fn = params.host_function_on(&snapshot)?;
+        let auth = auth::auth_entries(params.auth_mode, params.auth_entries.as_deref())?;
+        let prng_seed = match &params.prng_seed {
+            Some(seed) => seed.to_bytes()?,
+            None => [0; 32],
+        };
+        let snapshot = Rc::new(snapshot);
 
         let config_setup = ConfigSetup { 
             network_config: params.network_config, 
             adjustment_config: params.adjustment_config.unwrap_or(SimulationAdjustmentConfig::default_adjustment()), 
+            budget_limits: params.budget_limits.unwrap_or_default(),
         };        
 
-
-        Self { 
+        Ok(Self { 
             config_setup,
             host_fn, 
+            auth,
+            prng_seed,
             source_account: AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(params.source_account))), 
             snapshot
+        })
+    }
+
+    /// Strkey of the invoked contract, if the host function invokes one.
+    pub fn contract(&self) -> Option<String> {
+        match &self.host_fn {
+            HostFunction::InvokeContract(InvokeContractArgs {
+                contract_address: ScAddress::Contract(Hash(contract)),
+                ..
+            }) => Some(address::contract_strkey(contract)),
+            _ => None,
         }
     }
 
-    pub fn resolve(&self) -> InvokeHostFunctionSimulationResult {
-        let snapshot_source = self.snapshot.clone();
-
-        soroban_simulation::simulation::simulate_invoke_host_function_op(
-            snapshot_source.clone(), 
-            self.config_setup.network_config.clone(), 
-            &self.config_setup.adjustment_config, 
-            &snapshot_source.ledger_info(), 
-            self.host_fn.clone(), 
-            None, 
-            &self.source_account, 
-            [0; 32], 
-            true
-        ).unwrap()
+    /// The contract id or WASM hash created by a deployment host function.
+    pub fn created_id(&self, result: &SimulationResult) -> Option<String> {
+        host_function::created_id(&self.host_fn, &result.invoke_result)
+    }
+
+    pub fn source_account(&self) -> String {
+        let AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(account))) = &self.source_account;
+        address::account_strkey(account)
+    }
+
+    /// The base PRNG seed the invocation runs with.
+    pub fn prng_seed(&self) -> [u8; 32] {
+        self.prng_seed
+    }
+
+    pub fn resolve(&self) -> Result<SimulationResult, SoroflareError> {
+        let budget = self.budget()?;
+        let failed = |err: HostError| match SoroflareError::from_host_error(err, self.snapshot.take_missing_key()) {
+            SoroflareError::BudgetExceeded { error, .. } => SoroflareError::BudgetExceeded {
+                error,
+                usage: BudgetUsage::from_budget(&budget).ok(),
+            },
+            err => err,
+        };
+
+        let mut diagnostic_events = Vec::new();
+        let recording = e2e_invoke::invoke_host_function_in_recording_mode(
+            &budget,
+            true,
+            &self.host_fn,
+            &self.source_account,
+            self.auth.recording_mode(),
+            self.snapshot.ledger_info(),
+            self.snapshot.clone(),
+            self.prng_seed,
+            &mut diagnostic_events,
+        )
+        .map_err(failed)?;
+
+        let invoke_result = match &recording.invoke_result {
+            Ok(val) => val.clone(),
+            Err(err) => return Err(failed(err.clone())),
+        };
+
+        let (transaction_data, fees) = match &self.config_setup.network_config {
+            Some(config) => {
+                let (transaction_data, fees) = simulation::transaction_data(
+                    config,
+                    &self.config_setup.adjustment_config,
+                    &self.host_fn,
+                    &recording,
+                    self.snapshot.sequence_number,
+                )?;
+                (Some(transaction_data), Some(fees))
+            }
+            None => (None, None),
+        };
+        let usage = BudgetUsage::from_budget(&budget)?;
+
+        Ok(SimulationResult {
+            invoke_result,
+            modified_entries: simulation::modified_entries(&self.snapshot, &recording.ledger_changes)?,
+            ttl_changes: simulation::ttl_changes(&recording.ledger_changes)?,
+            auth: recording.auth,
+            contract_events: recording.contract_events,
+            diagnostic_events,
+            transaction_data,
+            fees,
+            simulated_instructions: usage.cpu_insns,
+            simulated_memory: usage.mem_bytes,
+            usage,
+            budget,
+        })
+    }
+
+    /// The budget the invocation runs with: the limits and cost params of the
+    /// network config, or the host defaults without one, lowered to the
+    /// budget limits.
+    fn budget(&self) -> Result<Budget, SoroflareError> {
+        let budget = match &self.config_setup.network_config {
+            Some(config) => Budget::try_from_configs(
+                config.tx_max_instructions as u64,
+                config.tx_memory_limit as u64,
+                config.cpu_cost_params.clone(),
+                config.memory_cost_params.clone(),
+            )
+            .map_err(|e| SoroflareError::Simulation(format!("invalid network config: {e:?}")))?,
+            None => Budget::default(),
+        };
+        self.config_setup.budget_limits.apply(&budget)?;
+
+        Ok(budget)
+    }
+
+    /// CPU and memory consumed by the run that produced `result`, per cost
+    /// type.
+    pub fn cost_breakdown(&self, result: &SimulationResult) -> Result<CostBreakdown, SoroflareError> {
+        CostBreakdown::from_budget(&result.budget, &self.config_setup.adjustment_config)
+    }
+
+    /// The snapshot with the ledger changes of `result` applied.
+    pub fn snapshot_after(&self, result: &SimulationResult) -> LedgerSnapshot {
+        let mut snapshot = self.snapshot.with_header_of(&self.snapshot);
+        snapshot.apply_changes(&result.modified_entries, &result.ttl_changes);
+        snapshot
+    }
+
+    /// Keys of the archived persistent entries the invocation accessed.
+    pub fn archived_entries(&self) -> Vec<LedgerKey> {
+        self.snapshot.archived_keys()
+    }
+
+    /// Simulates restoring the archived entries accessed by [`Self::resolve`].
+    /// Returns `None` when nothing needs to be restored.
+    pub fn restore_preamble(&self) -> Result<Option<RestoreOpSimulationResult>, SoroflareError> {
+        let keys_to_restore = self.snapshot.archived_keys();
+        if keys_to_restore.is_empty() {
+            return Ok(None);
+        }
+
+        let result = soroban_simulation::simulation::simulate_restore_op(
+            &self.snapshot.archive_view(),
+            self.config_setup.network_config.clone(),
+            &self.config_setup.adjustment_config,
+            &self.snapshot.ledger_info(),
+            &keys_to_restore,
+        ).map_err(|e| SoroflareError::Simulation(e.to_string()))?;
+
+        Ok(Some(result))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutils::{create_native_asset_contract, ledger_snapshot};
+
+    #[test]
+    fn prng_seeds_accept_hex_and_byte_arrays() {
+        let hex_seed: PrngSeed = serde_json::from_value(serde_json::json!(hex::encode([3; 32]))).unwrap();
+        assert_eq!(hex_seed.to_bytes().unwrap(), [3; 32]);
+
+        let byte_seed: PrngSeed = serde_json::from_value(serde_json::to_value([3u8; 32]).unwrap()).unwrap();
+        assert_eq!(byte_seed.to_bytes().unwrap(), [3; 32]);
+
+        let short_hex = PrngSeed::Hex(hex::encode([3; 31]));
+        assert!(matches!(short_hex.to_bytes(), Err(SoroflareError::InvalidParams(_))));
+        assert!(PrngSeed::Hex("not hex".into()).to_bytes().is_err());
+        assert!(serde_json::from_value::<PrngSeed>(serde_json::to_value([3u8; 31]).unwrap()).is_err());
+    }
+
+    #[test]
+    fn ledger_info_falls_back_to_the_file_then_the_network_config_then_defaults() {
+        let info = create_native_asset_contract(None).snapshot().ledger_info();
+        assert_eq!(info.protocol_version, DEFAULT_PROTOCOL_VERSION);
+        assert_eq!(info.base_reserve, DEFAULT_BASE_RESERVE);
+        assert_eq!(info.min_persistent_entry_ttl, DEFAULT_MIN_PERSISTENT_ENTRY_TTL);
+        assert_eq!(info.min_temp_entry_ttl, DEFAULT_MIN_TEMP_ENTRY_TTL);
+        assert_eq!(info.max_entry_ttl, DEFAULT_MAX_ENTRY_TTL);
+
+        let config = NetworkPreset::Futurenet.config(DEFAULT_PROTOCOL_VERSION).unwrap();
+        let info = create_native_asset_contract(Some(config.clone())).snapshot().ledger_info();
+        assert_eq!(info.min_persistent_entry_ttl, config.min_persistent_entry_ttl);
+        assert_eq!(info.min_temp_entry_ttl, config.min_temp_entry_ttl);
+        assert_eq!(info.max_entry_ttl, config.max_entry_ttl);
+
+        let mut file = ledger_snapshot(100);
+        file.base_reserve = 1;
+        file.min_persistent_entry_ttl = 500;
+        let with_file = || create_native_asset_contract(Some(config.clone())).with_snapshot(SnapshotFile::Ledger(file.clone()));
+        let info = with_file().snapshot().ledger_info();
+        assert_eq!(info.base_reserve, 1);
+        assert_eq!(info.min_persistent_entry_ttl, 500);
+        assert_eq!(info.max_entry_ttl, DEFAULT_MAX_ENTRY_TTL);
+
+        let params = with_file().with_ledger_info(LedgerInfoParams {
+            timestamp: Some(42),
+            min_persistent_entry_ttl: Some(7),
+            max_entry_ttl: Some(1_000),
+            ..Default::default()
+        });
+        let info = SoroflareInvocation::new(params).unwrap().snapshot.ledger_info();
+        assert_eq!(info.timestamp, 42);
+        assert_eq!(info.min_persistent_entry_ttl, 7);
+        assert_eq!(info.max_entry_ttl, 1_000);
+        assert_eq!(info.base_reserve, 1);
+        assert_eq!(info.sequence_number, 100);
+    }
+
+    #[test]
+    fn resource_fee_matches_fee_breakdown() {
+        let params = create_native_asset_contract(NetworkPreset::Testnet.config(DEFAULT_PROTOCOL_VERSION));
+        let result = SoroflareInvocation::new(params).unwrap().resolve().unwrap();
+
+        let transaction_data = result.transaction_data.unwrap();
+        let fees = result.fees.unwrap();
+        assert_eq!(fees.min_resource_fee, transaction_data.resource_fee);
+        // The created instance pays rent.
+        assert!(fees.rent_fee > 0);
+        assert_eq!(fees.rent_fee_per_entry.len(), 1);
+    }
+
+    #[test]
+    fn transaction_data_matches_upstream_simulation() {
+        let network_config = NetworkPreset::Testnet.config(DEFAULT_PROTOCOL_VERSION);
+        let invocation = SoroflareInvocation::new(create_native_asset_contract(network_config.clone())).unwrap();
+        let transaction_data = invocation.resolve().unwrap().transaction_data.unwrap();
+
+        let upstream = soroban_simulation::simulation::simulate_invoke_host_function_op(
+            invocation.snapshot.clone(),
+            network_config,
+            &invocation.config_setup.adjustment_config,
+            &invocation.snapshot.ledger_info(),
+            invocation.host_fn.clone(),
+            None,
+            &invocation.source_account,
+            invocation.prng_seed,
+            true,
+        )
+        .unwrap();
+        let expected = upstream.transaction_data.unwrap();
+
+        let resources = &transaction_data.resources;
+        let expected_resources = &expected.resources;
+        assert_eq!(resources.footprint, expected_resources.footprint);
+        assert_eq!(resources.instructions, expected_resources.instructions);
+        assert_eq!(resources.read_bytes, expected_resources.read_bytes);
+        assert_eq!(resources.write_bytes, expected_resources.write_bytes);
+        assert_eq!(transaction_data.resource_fee, expected.resource_fee);
+    }
+
+    #[test]
+    fn restore_preamble_restores_archived_entries() {
+        let network_config = NetworkPreset::Testnet.config(DEFAULT_PROTOCOL_VERSION);
+        let create = SoroflareInvocation::new(create_native_asset_contract(network_config.clone())).unwrap();
+        let created = create.resolve().unwrap();
+        let ScVal::Address(ScAddress::Contract(Hash(contract))) = created.invoke_result.clone() else {
+            panic!("no contract created");
+        };
+
+        let state = create.snapshot_after(&created).entries();
+        let (instance_key, (_, live_until)) = state
+            .iter()
+            .find(|(key, _)| matches!(key, LedgerKey::ContractData(_)))
+            .cloned()
+            .unwrap();
+
+        let params = SoroflareInvocationParams::new(
+            "decimals".into(),
+            contract,
+            Vec::new(),
+            [0; 32],
+            live_until.unwrap() + 1,
+            state,
+            None,
+            network_config,
+            None,
+        );
+        let invocation = SoroflareInvocation::new(params).unwrap();
+        invocation.resolve().unwrap();
+        assert_eq!(invocation.archived_entries(), vec![instance_key.clone()]);
+
+        let preamble = invocation.restore_preamble().unwrap().unwrap();
+        let footprint = &preamble.transaction_data.resources.footprint;
+        assert_eq!(footprint.read_write.to_vec(), vec![instance_key]);
+        assert!(preamble.transaction_data.resource_fee > 0);
     }
 }
@@ -0,0 +1,223 @@
+//! "Native" JSON rendering of `ScVal`s, following what `scValToNative` does in
+//! the JS SDK.
+//!
+//! 64 bit and wider integers become decimal strings, so clients don't lose
+//! precision, addresses become strkeys and bytes become hex. Maps whose keys
+//! are all symbols or strings become JSON objects, other maps become lists of
+//! `[key, value]` pairs.
+
+use serde_json::{json, Map, Value};
+use soroban_env_host::xdr::{
+    AccountId, Hash, Int128Parts, Int256Parts, PublicKey, ScAddress, ScMap, ScVal, UInt128Parts,
+    UInt256Parts, Uint256,
+};
+
+use crate::address;
+
+/// Decimal representation of an unsigned 256 bit integer, given as big endian
+/// 64 bit limbs.
+fn u256_to_string(mut limbs: [u64; 4]) -> String {
+    if limbs.iter().all(|limb| *limb == 0) {
+        return "0".into();
+    }
+
+    let mut digits = Vec::new();
+    while limbs.iter().any(|limb| *limb != 0) {
+        let mut remainder: u128 = 0;
+        for limb in limbs.iter_mut() {
+            let current = (remainder << 64) | *limb as u128;
+            *limb = (current / 10) as u64;
+            remainder = current % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+    }
+    digits.reverse();
+
+    String::from_utf8(digits).unwrap_or_default()
+}
+
+fn i256_to_string(parts: &Int256Parts) -> String {
+    let limbs = [parts.hi_hi as u64, parts.hi_lo, parts.lo_hi, parts.lo_lo];
+    if parts.hi_hi >= 0 {
+        return u256_to_string(limbs);
+    }
+
+    format!("-{}", u256_to_string(negate_limbs(limbs)))
+}
+
+/// Two's complement negation of a 256 bit integer given as big endian limbs.
+pub(crate) fn negate_limbs(limbs: [u64; 4]) -> [u64; 4] {
+    let mut negated = limbs.map(|limb| !limb);
+    for limb in negated.iter_mut().rev() {
+        let (sum, overflow) = limb.overflowing_add(1);
+        *limb = sum;
+        if !overflow {
+            break;
+        }
+    }
+
+    negated
+}
+
+/// Parses an unsigned decimal into big endian 64 bit limbs, `None` if it
+/// isn't a number or doesn't fit in 256 bits.
+pub(crate) fn parse_u256(decimal: &str) -> Option<[u64; 4]> {
+    if decimal.is_empty() {
+        return None;
+    }
+
+    let mut limbs = [0u64; 4];
+    for digit in decimal.chars() {
+        let mut carry = digit.to_digit(10)? as u128;
+        for limb in limbs.iter_mut().rev() {
+            let current = *limb as u128 * 10 + carry;
+            *limb = current as u64;
+            carry = current >> 64;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+
+    Some(limbs)
+}
+
+fn address_to_strkey(address: &ScAddress) -> String {
+    match address {
+        ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(account)))) => {
+            address::account_strkey(account)
+        }
+        ScAddress::Contract(Hash(contract)) => address::contract_strkey(contract),
+    }
+}
+
+fn map_to_native(map: &ScMap) -> Value {
+    let keys: Option<Vec<String>> = map
+        .0
+        .iter()
+        .map(|entry| match &entry.key {
+            ScVal::Symbol(symbol) => Some(symbol.0.to_utf8_string_lossy()),
+            ScVal::String(string) => Some(string.0.to_utf8_string_lossy()),
+            _ => None,
+        })
+        .collect();
+
+    match keys {
+        Some(keys) => Value::Object(
+            keys.into_iter()
+                .zip(map.0.iter())
+                .map(|(key, entry)| (key, to_native(&entry.val)))
+                .collect::<Map<_, _>>(),
+        ),
+        None => Value::Array(
+            map.0
+                .iter()
+                .map(|entry| json!([to_native(&entry.key), to_native(&entry.val)]))
+                .collect(),
+        ),
+    }
+}
+
+pub fn to_native(val: &ScVal) -> Value {
+    match val {
+        ScVal::Bool(b) => json!(b),
+        ScVal::Void => Value::Null,
+        ScVal::U32(n) => json!(n),
+        ScVal::I32(n) => json!(n),
+        ScVal::U64(n) => json!(n.to_string()),
+        ScVal::I64(n) => json!(n.to_string()),
+        ScVal::Timepoint(t) => json!(t.0.to_string()),
+        ScVal::Duration(d) => json!(d.0.to_string()),
+        ScVal::U128(UInt128Parts { hi, lo }) => json!((((*hi as u128) << 64) | *lo as u128).to_string()),
+        ScVal::I128(Int128Parts { hi, lo }) => json!((((*hi as i128) << 64) | *lo as i128).to_string()),
+        ScVal::U256(UInt256Parts { hi_hi, hi_lo, lo_hi, lo_lo }) => {
+            json!(u256_to_string([*hi_hi, *hi_lo, *lo_hi, *lo_lo]))
+        }
+        ScVal::I256(parts) => json!(i256_to_string(parts)),
+        ScVal::Bytes(bytes) => json!(hex::encode(bytes.0.as_slice())),
+        ScVal::String(string) => json!(string.0.to_utf8_string_lossy()),
+        ScVal::Symbol(symbol) => json!(symbol.0.to_utf8_string_lossy()),
+        ScVal::Vec(Some(vec)) => Value::Array(vec.0.iter().map(to_native).collect()),
+        ScVal::Vec(None) => Value::Array(vec![]),
+        ScVal::Map(Some(map)) => map_to_native(map),
+        ScVal::Map(None) => Value::Object(Map::new()),
+        ScVal::Address(address) => json!(address_to_strkey(address)),
+        // Errors, nonces and contract instances have no native counterpart.
+        other => serde_json::to_value(other).unwrap_or(Value::Null),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use soroban_env_host::xdr::{ScBytes, ScMapEntry, ScSymbol, ScVec};
+
+    use super::*;
+
+    fn symbol(symbol: &str) -> ScVal {
+        ScVal::Symbol(ScSymbol(symbol.try_into().unwrap()))
+    }
+
+    fn map(entries: Vec<(ScVal, ScVal)>) -> ScVal {
+        let entries: Vec<_> = entries.into_iter().map(|(key, val)| ScMapEntry { key, val }).collect();
+        ScVal::Map(Some(ScMap(entries.try_into().unwrap())))
+    }
+
+    #[test]
+    fn wide_integers_render_as_decimal_strings() {
+        assert_eq!(
+            to_native(&ScVal::I128(Int128Parts { hi: -1, lo: u64::MAX - 999 })),
+            json!("-1000")
+        );
+        assert_eq!(
+            to_native(&ScVal::U256(UInt256Parts { hi_hi: 0, hi_lo: 0, lo_hi: 1, lo_lo: 0 })),
+            json!("18446744073709551616")
+        );
+        assert_eq!(
+            to_native(&ScVal::I256(Int256Parts {
+                hi_hi: -1,
+                hi_lo: u64::MAX,
+                lo_hi: u64::MAX,
+                lo_lo: u64::MAX - 41,
+            })),
+            json!("-42")
+        );
+    }
+
+    #[test]
+    fn addresses_render_as_strkeys_and_bytes_as_hex() {
+        assert_eq!(
+            to_native(&ScVal::Address(ScAddress::Contract(Hash([7; 32])))),
+            json!(address::contract_strkey(&[7; 32]))
+        );
+        assert_eq!(
+            to_native(&ScVal::Address(ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+                [1; 32]
+            )))))),
+            json!("GAAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQDZ7H")
+        );
+        assert_eq!(
+            to_native(&ScVal::Bytes(ScBytes(vec![0xde, 0xad, 0xbe, 0xef].try_into().unwrap()))),
+            json!("deadbeef")
+        );
+    }
+
+    #[test]
+    fn maps_with_symbol_keys_render_as_objects() {
+        let balances = map(vec![
+            (symbol("amount"), ScVal::I64(10)),
+            (symbol("frozen"), ScVal::Bool(false)),
+        ]);
+
+        assert_eq!(to_native(&balances), json!({ "amount": "10", "frozen": false }));
+    }
+
+    #[test]
+    fn maps_with_other_keys_render_as_pairs() {
+        let owners = map(vec![
+            (ScVal::U32(1), symbol("alice")),
+            (symbol("two"), ScVal::Vec(Some(ScVec(vec![ScVal::U32(2)].try_into().unwrap())))),
+        ]);
+
+        assert_eq!(to_native(&owners), json!([[1, "alice"], ["two", [2]]]));
+    }
+}
@@ -0,0 +1,348 @@
+//! Built-in network configurations, so callers don't need to paste the whole
+//! cost model into every request.
+//!
+//! `network_config` accepts a full `NetworkConfig`, a preset name such as
+//! `"testnet"`, or an object naming a `preset` with field overrides:
+//!
+//! ```json
+//! { "preset": "testnet", "protocol_version": 20, "tx_max_instructions": 200000000 }
+//! ```
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use soroban_env_host::{
+    fees::{FeeConfiguration, RentFeeConfiguration},
+    xdr::{ContractCostParamEntry, ContractCostParams, ExtensionPoint},
+};
+use soroban_simulation::NetworkConfig;
+
+use crate::{DEFAULT_MAX_ENTRY_TTL, DEFAULT_MIN_PERSISTENT_ENTRY_TTL, DEFAULT_MIN_TEMP_ENTRY_TTL, DEFAULT_PROTOCOL_VERSION};
+
+/// `(const_term, linear_term)` of the CPU cost model, in `ContractCostType`
+/// order, as voted in for protocol 20.
+const CPU_COST_PARAMS_V20: [(i64, i64); 23] = [
+    (4, 0),
+    (434, 16),
+    (42, 16),
+    (44, 16),
+    (295, 0),
+    (60, 0),
+    (221, 26),
+    (331, 4369),
+    (3636, 7013),
+    (40256, 0),
+    (377551, 4059),
+    (417482, 45712),
+    (417482, 45712),
+    (1945, 0),
+    (6481, 5943),
+    (711, 0),
+    (2314804, 0),
+    (4176, 0),
+    (4716, 0),
+    (4680, 0),
+    (4256, 0),
+    (884, 0),
+    (1059, 502),
+];
+
+/// `(const_term, linear_term)` of the memory cost model for protocol 20.
+const MEMORY_COST_PARAMS_V20: [(i64, i64); 23] = [
+    (0, 0),
+    (16, 128),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (242, 384),
+    (0, 384),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    (132773, 4903),
+    (132773, 4903),
+    (14, 0),
+    (0, 0),
+    (0, 0),
+    (181, 0),
+    (99, 0),
+    (99, 0),
+    (99, 0),
+    (99, 0),
+    (99, 0),
+    (0, 0),
+];
+
+fn cost_params(params: &[(i64, i64)]) -> ContractCostParams {
+    ContractCostParams(
+        params
+            .iter()
+            .map(|&(const_term, linear_term)| ContractCostParamEntry {
+                ext: ExtensionPoint::V0,
+                const_term,
+                linear_term,
+            })
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap_or_default(),
+    )
+}
+
+/// Fee rates for instructions, entry reads and writes, events and transaction
+/// size. The write fee per KB is derived from the bucket list size, so it is
+/// kept per network.
+fn fee_configuration(fee_per_write_1kb: i64) -> FeeConfiguration {
+    FeeConfiguration {
+        fee_per_instruction_increment: 25,
+        fee_per_read_entry: 6250,
+        fee_per_write_entry: 10000,
+        fee_per_read_1kb: 1786,
+        fee_per_write_1kb,
+        fee_per_historical_1kb: 16235,
+        fee_per_contract_event_1kb: 10000,
+        fee_per_transaction_size_1kb: 1624,
+    }
+}
+
+/// The settings of a network for one protocol version that the presets don't
+/// share.
+struct NetworkSettings {
+    fee_per_write_1kb: i64,
+    persistent_rent_rate_denominator: i64,
+    temporary_rent_rate_denominator: i64,
+    min_persistent_entry_ttl: u32,
+    min_temp_entry_ttl: u32,
+    max_entry_ttl: u32,
+}
+
+/// CPU and memory limits of a transaction, the same on every network.
+const TX_MAX_INSTRUCTIONS_V20: i64 = 100_000_000;
+const TX_MEMORY_LIMIT_V20: u32 = 41_943_040;
+
+const PUBNET_V20: NetworkSettings = NetworkSettings {
+    fee_per_write_1kb: 11800,
+    persistent_rent_rate_denominator: 2103,
+    temporary_rent_rate_denominator: 4206,
+    min_persistent_entry_ttl: 120_960,
+    min_temp_entry_ttl: 17_280,
+    max_entry_ttl: 3_110_400,
+};
+
+const TESTNET_V20: NetworkSettings = NetworkSettings {
+    fee_per_write_1kb: 1786,
+    persistent_rent_rate_denominator: 1402,
+    temporary_rent_rate_denominator: 2804,
+    min_persistent_entry_ttl: DEFAULT_MIN_PERSISTENT_ENTRY_TTL,
+    min_temp_entry_ttl: DEFAULT_MIN_TEMP_ENTRY_TTL,
+    max_entry_ttl: DEFAULT_MAX_ENTRY_TTL,
+};
+
+/// Futurenet prices like testnet, but keeps stellar-core's initial TTLs.
+const FUTURENET_V20: NetworkSettings = NetworkSettings {
+    min_persistent_entry_ttl: 4096,
+    min_temp_entry_ttl: 16,
+    max_entry_ttl: 535_680,
+    ..TESTNET_V20
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkPreset {
+    Pubnet,
+    Testnet,
+    Futurenet,
+    /// The pubnet settings without CPU and memory limits, for measuring
+    /// invocations that don't fit in a transaction.
+    Unlimited,
+}
+
+impl NetworkPreset {
+    /// The network settings for `protocol_version`, `None` for protocols this
+    /// build doesn't carry settings for.
+    ///
+    /// These are the settings each network ran protocol 20 with. Networks can
+    /// vote on new settings at any time, so pass overrides to follow them.
+    pub fn config(self, protocol_version: u32) -> Option<NetworkConfig> {
+        let (cpu_cost_params, memory_cost_params) = match protocol_version {
+            20 => (&CPU_COST_PARAMS_V20, &MEMORY_COST_PARAMS_V20),
+            _ => return None,
+        };
+        let settings = match (self, protocol_version) {
+            (Self::Pubnet | Self::Unlimited, 20) => &PUBNET_V20,
+            (Self::Testnet, 20) => &TESTNET_V20,
+            (Self::Futurenet, 20) => &FUTURENET_V20,
+            _ => return None,
+        };
+        let (tx_max_instructions, tx_memory_limit) = match self {
+            Self::Unlimited => (i64::MAX, u32::MAX),
+            _ => (TX_MAX_INSTRUCTIONS_V20, TX_MEMORY_LIMIT_V20),
+        };
+
+        Some(NetworkConfig {
+            fee_configuration: fee_configuration(settings.fee_per_write_1kb),
+            rent_fee_configuration: RentFeeConfiguration {
+                fee_per_write_1kb: settings.fee_per_write_1kb,
+                fee_per_write_entry: 10000,
+                persistent_rent_rate_denominator: settings.persistent_rent_rate_denominator,
+                temporary_rent_rate_denominator: settings.temporary_rent_rate_denominator,
+            },
+            tx_max_instructions,
+            tx_memory_limit,
+            cpu_cost_params: cost_params(cpu_cost_params),
+            memory_cost_params: cost_params(memory_cost_params),
+            min_temp_entry_ttl: settings.min_temp_entry_ttl,
+            min_persistent_entry_ttl: settings.min_persistent_entry_ttl,
+            max_entry_ttl: settings.max_entry_ttl,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct PresetWithOverrides {
+    preset: NetworkPreset,
+    protocol_version: Option<u32>,
+    #[serde(flatten)]
+    overrides: Map<String, Value>,
+}
+
+/// Merges `overrides` into `base`, recursing into nested objects. Fields that
+/// `base` doesn't have are rejected.
+fn merge(base: &mut Map<String, Value>, overrides: Map<String, Value>, path: &str) -> Result<(), String> {
+    for (key, value) in overrides {
+        let field = format!("{path}{key}");
+
+        match (base.get_mut(&key), value) {
+            (None, _) => return Err(format!("unknown network config field `{field}`")),
+            (Some(Value::Object(base)), Value::Object(value)) => merge(base, value, &format!("{field}."))?,
+            (Some(base), value) => *base = value,
+        }
+    }
+
+    Ok(())
+}
+
+fn with_overrides(params: PresetWithOverrides) -> Result<NetworkConfig, String> {
+    let protocol_version = params.protocol_version.unwrap_or(DEFAULT_PROTOCOL_VERSION);
+    let config = params
+        .preset
+        .config(protocol_version)
+        .ok_or_else(|| format!("no {:?} preset for protocol {protocol_version}", params.preset))?;
+
+    let Value::Object(mut config) = serde_json::to_value(config).map_err(|e| e.to_string())? else {
+        return Err("network config isn't an object".into());
+    };
+    merge(&mut config, params.overrides, "")?;
+
+    serde_json::from_value(Value::Object(config)).map_err(|e| format!("invalid network config override: {e}"))
+}
+
+/// Resolves a `network_config` param. The shape picks the form, so that a
+/// malformed config gets the error of the form it was meant as.
+fn config_from_value(value: Value) -> Result<NetworkConfig, String> {
+    match value {
+        Value::String(name) => {
+            let preset: NetworkPreset = serde_json::from_value(Value::String(name))
+                .map_err(|e| format!("invalid network config preset: {e}"))?;
+            preset
+                .config(DEFAULT_PROTOCOL_VERSION)
+                .ok_or_else(|| format!("no {preset:?} preset for protocol {DEFAULT_PROTOCOL_VERSION}"))
+        }
+        Value::Object(config) if config.contains_key("preset") => {
+            let params = serde_json::from_value(Value::Object(config))
+                .map_err(|e| format!("invalid network config preset: {e}"))?;
+            with_overrides(params)
+        }
+        Value::Object(config) => {
+            serde_json::from_value(Value::Object(config)).map_err(|e| format!("invalid network config: {e}"))
+        }
+        _ => Err("network config must be a preset name, an object with a `preset`, or a full config".into()),
+    }
+}
+
+/// Deserializes the params' `network_config`, resolving presets.
+pub fn deserialize_config<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NetworkConfig>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        Some(value) => config_from_value(value).map(Some).map_err(D::Error::custom),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Params {
+        #[serde(deserialize_with = "deserialize_config", default)]
+        network_config: Option<NetworkConfig>,
+    }
+
+    fn network_config(value: Value) -> Result<Option<NetworkConfig>, serde_json::Error> {
+        serde_json::from_value::<Params>(json!({ "network_config": value })).map(|params| params.network_config)
+    }
+
+    #[test]
+    fn presets_carry_each_networks_settings() {
+        let presets = [
+            NetworkPreset::Pubnet,
+            NetworkPreset::Testnet,
+            NetworkPreset::Futurenet,
+            NetworkPreset::Unlimited,
+        ];
+        for preset in presets {
+            assert!(preset.config(DEFAULT_PROTOCOL_VERSION).is_some(), "{preset:?}");
+        }
+
+        let testnet = network_config(json!("testnet")).unwrap().unwrap();
+        assert_eq!(testnet.rent_fee_configuration.persistent_rent_rate_denominator, 1402);
+        assert_eq!(testnet.min_persistent_entry_ttl, DEFAULT_MIN_PERSISTENT_ENTRY_TTL);
+
+        let pubnet = NetworkPreset::Pubnet.config(DEFAULT_PROTOCOL_VERSION).unwrap();
+        assert_eq!(pubnet.rent_fee_configuration.persistent_rent_rate_denominator, 2103);
+        assert_eq!(pubnet.fee_configuration.fee_per_write_1kb, 11800);
+        assert_eq!(pubnet.min_persistent_entry_ttl, 120_960);
+
+        let futurenet = NetworkPreset::Futurenet.config(DEFAULT_PROTOCOL_VERSION).unwrap();
+        assert_eq!(futurenet.fee_configuration.fee_per_write_1kb, testnet.fee_configuration.fee_per_write_1kb);
+        assert_eq!(futurenet.max_entry_ttl, 535_680);
+
+        let unlimited = NetworkPreset::Unlimited.config(DEFAULT_PROTOCOL_VERSION).unwrap();
+        assert_eq!(unlimited.fee_configuration.fee_per_write_1kb, pubnet.fee_configuration.fee_per_write_1kb);
+        assert_eq!(unlimited.tx_max_instructions, i64::MAX);
+    }
+
+    #[test]
+    fn presets_accept_field_overrides() {
+        let config = network_config(json!({
+            "preset": "testnet",
+            "tx_max_instructions": 200_000_000,
+            "fee_configuration": { "fee_per_write_entry": 1 },
+        }))
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(config.tx_max_instructions, 200_000_000);
+        assert_eq!(config.fee_configuration.fee_per_write_entry, 1);
+        assert_eq!(config.fee_configuration.fee_per_read_entry, 6250);
+
+        assert!(network_config(json!("unlimited")).unwrap().is_some());
+        assert!(network_config(json!({ "preset": "testnet", "tx_max_instructionz": 1 })).is_err());
+        assert!(network_config(json!({ "preset": "testnet", "protocol_version": 19 })).is_err());
+    }
+
+    #[test]
+    fn malformed_configs_name_the_problem() {
+        let error = network_config(json!({ "tx_max_instructions": 1 })).unwrap_err().to_string();
+        assert!(error.contains("invalid network config: missing field"), "{error}");
+
+        let error = network_config(json!("mainnet")).unwrap_err().to_string();
+        assert!(error.contains("invalid network config preset: unknown variant `mainnet`"), "{error}");
+
+        let error = network_config(json!(20)).unwrap_err().to_string();
+        assert!(error.contains("must be a preset name"), "{error}");
+
+        assert!(network_config(Value::Null).unwrap().is_none());
+    }
+}
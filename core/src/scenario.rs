@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{snapshot::LedgerSnapshot, SimulationResult, SoroflareError, SoroflareInvocation, SoroflareInvocationParams};
+
+/// An ordered list of invocations sharing their ledger state: every step runs
+/// on top of the ledger changes of the steps before it.
+///
+/// The first step's ledger entries are the initial state, the entries of later
+/// steps are layered over the carried state as overrides. Later steps also
+/// carry the ledger header, so omitting `ledger_sequence` or `network` keeps
+/// the previous step's values.
+#[derive(Serialize, Deserialize)]
+pub struct SoroflareScenario {
+    steps: Vec<SoroflareInvocationParams>,
+}
+
+pub struct ScenarioStep {
+    pub invocation: SoroflareInvocation,
+    pub result: SimulationResult,
+}
+
+impl SoroflareScenario {
+    pub fn new(steps: Vec<SoroflareInvocationParams>) -> Self {
+        Self { steps }
+    }
+
+    pub fn steps_mut(&mut self) -> &mut [SoroflareInvocationParams] {
+        &mut self.steps
+    }
+
+    /// Runs all steps in order, returning their results and the final ledger
+    /// state. Stops at the first failing step and returns its index.
+    pub fn resolve(self) -> Result<(Vec<ScenarioStep>, LedgerSnapshot), (usize, SoroflareError)> {
+        let mut state: Option<LedgerSnapshot> = None;
+        let mut steps = Vec::with_capacity(self.steps.len());
+
+        for (idx, params) in self.steps.into_iter().enumerate() {
+            let invocation = match &state {
+                Some(state) => SoroflareInvocation::new_on(params, state),
+                None => SoroflareInvocation::new(params),
+            }
+            .map_err(|e| (idx, e))?;
+            let result = invocation.resolve().map_err(|e| (idx, e))?;
+
+            state = Some(invocation.snapshot_after(&result));
+            steps.push(ScenarioStep { invocation, result });
+        }
+
+        Ok((steps, state.unwrap_or_default()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use soroban_env_host::xdr::ScVal;
+
+    use super::*;
+    use crate::{
+        snapshot::{hashed_network_id, SnapshotFile},
+        testutils::{create_native_asset_contract, ledger_snapshot, native_asset_contract, persistent_data, NETWORK},
+    };
+
+    #[test]
+    fn first_step_keeps_its_snapshot_file_entries() {
+        let (key, entry) = persistent_data(1, 1);
+        let mut file = ledger_snapshot(100);
+        file.insert(key.clone(), entry.clone(), Some(5000));
+
+        let scenario = SoroflareScenario::new(vec![
+            create_native_asset_contract(None).with_snapshot(SnapshotFile::Ledger(file)),
+        ]);
+        let (steps, state) = scenario.resolve().map_err(|(_, e)| e).unwrap();
+
+        assert!(steps[0].invocation.snapshot.ledger_entries.contains_key(&key));
+        assert_eq!(state.ledger_entries.get(&key).map(|(e, live_until)| (&**e, *live_until)), Some((&entry, Some(5000))));
+        assert_eq!(state.ledger_entries.len(), 2);
+    }
+
+    #[test]
+    fn later_steps_layer_stored_snapshots() {
+        let (key, entry) = persistent_data(1, 1);
+        let mut stored = ledger_snapshot(200);
+        stored.insert(key.clone(), entry, Some(5000));
+
+        let mut decimals = SoroflareInvocationParams::new(
+            "decimals".into(),
+            native_asset_contract(),
+            Vec::new(),
+            [0; 32],
+            200,
+            Vec::new(),
+            None,
+            None,
+            None,
+        );
+        decimals.resolve_snapshot_id(stored).unwrap();
+
+        let scenario = SoroflareScenario::new(vec![create_native_asset_contract(None), decimals]);
+        let (steps, state) = scenario.resolve().map_err(|(_, e)| e).unwrap();
+
+        assert_eq!(steps[1].result.invoke_result, ScVal::U32(7));
+        assert!(state.ledger_entries.contains_key(&key));
+    }
+
+    fn decimals(overrides: serde_json::Value) -> SoroflareInvocationParams {
+        let mut params = json!({
+            "fname": "decimals",
+            "contract": hex::encode(native_asset_contract()),
+            "source_account": hex::encode([0; 32]),
+        });
+        params.as_object_mut().unwrap().extend(overrides.as_object().unwrap().clone());
+
+        serde_json::from_value(params).unwrap()
+    }
+
+    #[test]
+    fn steps_carry_the_ledger_header() {
+        let scenario = SoroflareScenario::new(vec![create_native_asset_contract(None), decimals(json!({}))]);
+        let (steps, _) = scenario.resolve().map_err(|(_, e)| e).unwrap();
+
+        let header = &steps[1].invocation.snapshot;
+        assert_eq!(header.sequence_number, 100);
+        assert_eq!(header.network_id, hashed_network_id(NETWORK));
+        assert_eq!(steps[1].result.invoke_result, ScVal::U32(7));
+    }
+
+    #[test]
+    fn steps_override_the_carried_header() {
+        let scenario = SoroflareScenario::new(vec![
+            create_native_asset_contract(None),
+            decimals(json!({ "ledger_sequence": 150 })),
+            decimals(json!({ "network": "Other Network" })),
+        ]);
+        let (steps, _) = scenario.resolve().map_err(|(_, e)| e).unwrap();
+
+        let header = &steps[1].invocation.snapshot;
+        assert_eq!(header.sequence_number, 150);
+        assert_eq!(header.network_id, hashed_network_id(NETWORK));
+
+        let header = &steps[2].invocation.snapshot;
+        assert_eq!(header.sequence_number, 150);
+        assert_eq!(header.network_id, hashed_network_id("Other Network"));
+    }
+}
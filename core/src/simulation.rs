@@ -0,0 +1,259 @@
+//! The result of soroflare's recording-mode run of a host function, and the
+//! transaction data derived from it.
+//!
+//! soroflare drives the host directly instead of going through
+//! `simulate_invoke_host_function_op`, which builds its own budget: that way
+//! the budget limits, the consumed budget and the cost breakdown all belong to
+//! the one run that produced the result. The resources, transaction size
+//! estimate and resource fee follow soroban-simulation.
+
+use serde::{Serialize, Serializer};
+use soroban_env_host::{
+    budget::Budget,
+    e2e_invoke::{self, InvokeHostFunctionRecordingModeResult, LedgerEntryChange},
+    fees::{LedgerEntryRentChange, TransactionResources},
+    xdr::{
+        ContractEvent, DecoratedSignature, DiagnosticEvent, ExtensionPoint, HostFunction,
+        InvokeHostFunctionOp, LedgerEntry, LedgerFootprint, LedgerKey, Limits, Memo, MuxedAccount,
+        MuxedAccountMed25519, Operation, OperationBody, Preconditions, ReadXdr, ScVal, SequenceNumber,
+        Signature, SignatureHint, SorobanAuthorizationEntry, SorobanResources, SorobanTransactionData,
+        Transaction, TransactionEnvelope, TransactionExt, TransactionV1Envelope, Uint256, WriteXdr,
+    },
+};
+use soroban_simulation::{
+    simulation::{SimulationAdjustmentConfig, SimulationAdjustmentFactor},
+    NetworkConfig,
+};
+
+use crate::{snapshot::LedgerSnapshot, BudgetUsage, FeeBreakdown, SoroflareError};
+
+/// Signatures counted in the transaction size estimate.
+const MAX_SIGNATURES: usize = 20;
+
+fn xdr_error(err: soroban_env_host::xdr::Error) -> SoroflareError {
+    SoroflareError::Simulation(format!("failed to convert xdr: {err}"))
+}
+
+/// Pads a measured resource like the simulation does:
+/// `max(value + additive, floor(value * multiplicative))`.
+pub(crate) fn adjusted(value: u64, factor: &SimulationAdjustmentFactor) -> u64 {
+    value
+        .saturating_add(u64::from(factor.additive_factor))
+        .max((value as f64 * factor.multiplicative_factor).floor() as u64)
+}
+
+fn adjusted_u32(value: u32, factor: &SimulationAdjustmentFactor) -> u32 {
+    adjusted(value.into(), factor).try_into().unwrap_or(u32::MAX)
+}
+
+/// An entry written by the invocation. `None` is a missing entry, so a created
+/// entry has no `state_before` and a deleted one no `state_after`.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct LedgerEntryDiff {
+    pub state_before: Option<LedgerEntry>,
+    pub state_after: Option<LedgerEntry>,
+}
+
+/// A TTL set by the invocation, either extended or, for created entries,
+/// the initial one.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct TtlChange {
+    pub key: LedgerKey,
+    pub old_live_until_ledger: u32,
+    pub new_live_until_ledger: u32,
+}
+
+/// Serializes the return value as `{"Ok": ...}`, like the soroban-simulation
+/// result soroflare used to return.
+fn serialize_ok<S: Serializer>(value: &ScVal, serializer: S) -> Result<S::Ok, S::Error> {
+    Ok::<_, ()>(value).serialize(serializer)
+}
+
+#[derive(Serialize)]
+pub struct SimulationResult {
+    #[serde(serialize_with = "serialize_ok")]
+    pub invoke_result: ScVal,
+    /// Recorded or enforced authorization entries.
+    pub auth: Vec<SorobanAuthorizationEntry>,
+    /// Raw event streams, left out of the JSON: responses carry them decoded
+    /// and filtered instead.
+    #[serde(skip)]
+    pub contract_events: Vec<ContractEvent>,
+    #[serde(skip)]
+    pub diagnostic_events: Vec<DiagnosticEvent>,
+    /// Transaction data with the adjusted resources and the resource fee,
+    /// `None` without a network config to price it with.
+    pub transaction_data: Option<SorobanTransactionData>,
+    /// Breakdown of the transaction data's resource fee.
+    pub fees: Option<FeeBreakdown>,
+    pub simulated_instructions: u64,
+    pub simulated_memory: u64,
+    pub modified_entries: Vec<LedgerEntryDiff>,
+    /// TTLs of the written and read entries that changed.
+    pub ttl_changes: Vec<TtlChange>,
+    /// CPU and memory the run consumed, out of the limits it had.
+    pub usage: BudgetUsage,
+    /// The budget of the run, for the cost breakdown.
+    #[serde(skip)]
+    pub(crate) budget: Budget,
+}
+
+/// The written entries whose value changed, with their state in `snapshot`.
+pub(crate) fn modified_entries(
+    snapshot: &LedgerSnapshot,
+    changes: &[LedgerEntryChange],
+) -> Result<Vec<LedgerEntryDiff>, SoroflareError> {
+    let mut entries = Vec::new();
+
+    for change in changes.iter().filter(|change| !change.read_only) {
+        let key = LedgerKey::from_xdr(&change.encoded_key, Limits::none()).map_err(xdr_error)?;
+        let state_before = snapshot.ledger_entries.get(&key).map(|(entry, _)| (**entry).clone());
+        let state_after = change
+            .encoded_new_value
+            .as_ref()
+            .map(|value| LedgerEntry::from_xdr(value, Limits::none()))
+            .transpose()
+            .map_err(xdr_error)?;
+
+        if state_before != state_after {
+            entries.push(LedgerEntryDiff {
+                state_before,
+                state_after,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// The TTL changes of the entries that still exist after the invocation.
+pub(crate) fn ttl_changes(changes: &[LedgerEntryChange]) -> Result<Vec<TtlChange>, SoroflareError> {
+    let mut ttl_changes = Vec::new();
+
+    for change in changes.iter().filter(|change| change.encoded_new_value.is_some()) {
+        let Some(ttl_change) = &change.ttl_change else {
+            continue;
+        };
+        if ttl_change.new_live_until_ledger == ttl_change.old_live_until_ledger {
+            continue;
+        }
+
+        ttl_changes.push(TtlChange {
+            key: LedgerKey::from_xdr(&change.encoded_key, Limits::none()).map_err(xdr_error)?,
+            old_live_until_ledger: ttl_change.old_live_until_ledger,
+            new_live_until_ledger: ttl_change.new_live_until_ledger,
+        });
+    }
+
+    Ok(ttl_changes)
+}
+
+/// Size of the largest transaction envelope that can carry `operation`: a
+/// muxed source, the longest text memo and the most signatures a transaction
+/// can have.
+fn estimate_transaction_size(operation: OperationBody, footprint: &LedgerFootprint) -> Result<u32, SoroflareError> {
+    let source = MuxedAccount::MuxedEd25519(MuxedAccountMed25519 {
+        id: 0,
+        ed25519: Uint256([0; 32]),
+    });
+    let signature = DecoratedSignature {
+        hint: SignatureHint([0; 4]),
+        signature: Signature([0; 64].to_vec().try_into().map_err(xdr_error)?),
+    };
+
+    let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+        tx: Transaction {
+            source_account: source.clone(),
+            fee: 0,
+            seq_num: SequenceNumber(0),
+            cond: Preconditions::None,
+            memo: Memo::Text([0; 28].to_vec().try_into().map_err(xdr_error)?),
+            operations: vec![Operation {
+                source_account: Some(source),
+                body: operation,
+            }]
+            .try_into()
+            .map_err(xdr_error)?,
+            ext: TransactionExt::V1(SorobanTransactionData {
+                ext: ExtensionPoint::V0,
+                resources: SorobanResources {
+                    footprint: footprint.clone(),
+                    instructions: 0,
+                    read_bytes: 0,
+                    write_bytes: 0,
+                },
+                resource_fee: 0,
+            }),
+        },
+        signatures: vec![signature; MAX_SIGNATURES].try_into().map_err(xdr_error)?,
+    });
+
+    let size = envelope.to_xdr(Limits::none()).map_err(xdr_error)?.len();
+    Ok(size.try_into().unwrap_or(u32::MAX))
+}
+
+/// The rent changes of the recorded run, with the key of every entry. These
+/// come from the host's TTL changes, so extended entries are included.
+fn rent_changes(changes: &[LedgerEntryChange]) -> Result<Vec<(LedgerKey, LedgerEntryRentChange)>, SoroflareError> {
+    let mut rent_changes = Vec::new();
+
+    for change in changes {
+        if let Some(rent_change) = e2e_invoke::extract_rent_changes(std::slice::from_ref(change)).pop() {
+            let key = LedgerKey::from_xdr(&change.encoded_key, Limits::none()).map_err(xdr_error)?;
+            rent_changes.push((key, rent_change));
+        }
+    }
+
+    Ok(rent_changes)
+}
+
+/// Transaction data for the recorded run, with the resources padded by
+/// `adjustment` and the resource fee priced with `config`, and the breakdown
+/// of that fee.
+pub(crate) fn transaction_data(
+    config: &NetworkConfig,
+    adjustment: &SimulationAdjustmentConfig,
+    host_fn: &HostFunction,
+    recording: &InvokeHostFunctionRecordingModeResult,
+    ledger_sequence: u32,
+) -> Result<(SorobanTransactionData, FeeBreakdown), SoroflareError> {
+    let mut resources = recording.resources.clone();
+    resources.instructions = adjusted_u32(resources.instructions, &adjustment.instructions);
+    resources.read_bytes = adjusted_u32(resources.read_bytes, &adjustment.read_bytes);
+    resources.write_bytes = adjusted_u32(resources.write_bytes, &adjustment.write_bytes);
+
+    let operation = OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+        host_function: host_fn.clone(),
+        auth: recording.auth.clone().try_into().map_err(xdr_error)?,
+    });
+    let transaction_size_bytes = adjusted_u32(
+        estimate_transaction_size(operation, &resources.footprint)?,
+        &adjustment.tx_size,
+    );
+
+    let footprint = &resources.footprint;
+    let transaction_resources = TransactionResources {
+        instructions: resources.instructions,
+        read_entries: (footprint.read_only.len() + footprint.read_write.len()) as u32,
+        write_entries: footprint.read_write.len() as u32,
+        read_bytes: resources.read_bytes,
+        write_bytes: resources.write_bytes,
+        contract_events_size_bytes: recording.contract_events_and_return_value_size,
+        transaction_size_bytes,
+    };
+
+    let fees = FeeBreakdown::new(
+        config,
+        &adjustment.refundable_fee,
+        &transaction_resources,
+        &rent_changes(&recording.ledger_changes)?,
+        ledger_sequence,
+    );
+    let transaction_data = SorobanTransactionData {
+        ext: ExtensionPoint::V0,
+        resources,
+        resource_fee: fees.min_resource_fee,
+    };
+
+    Ok((transaction_data, fees))
+}
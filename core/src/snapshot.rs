@@ -1,14 +1,133 @@
-use std::rc::Rc;
+use std::{cell::RefCell, rc::Rc};
 
-use serde::{Deserialize, Serialize};
+use indexmap::IndexMap;
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 use sha2::{Digest, Sha256};
-use soroban_env_host::{storage::SnapshotSource, xdr::{LedgerEntry, LedgerKey, ScError, ScErrorCode}, LedgerInfo};
+use soroban_env_host::{
+    storage::SnapshotSource,
+    xdr::{
+        ContractDataDurability, ContractExecutable, Hash, LedgerEntry, LedgerEntryData, LedgerKey,
+        LedgerKeyAccount, LedgerKeyContractCode, LedgerKeyContractData, LedgerKeyTrustLine,
+        ScAddress, ScVal,
+    },
+    LedgerInfo,
+};
+
+use crate::{LedgerEntryDiff, TtlChange};
+
+/// Ledger entries and their `live_until` ledger, keyed by ledger key. Iteration
+/// follows insertion order, so snapshots serialize deterministically.
+pub type LedgerEntries = IndexMap<LedgerKey, (Box<LedgerEntry>, Option<u32>)>;
 
 pub fn hashed_network_id(passphrase: &str) -> [u8; 32] {
     Sha256::digest(passphrase.as_bytes()).into()
 }
 
-#[derive(Debug, Default, Clone)]
+/// Serialized form of [`LedgerSnapshot`], matching the ledger snapshots written
+/// by the soroban CLI and SDK.
+#[derive(Serialize, Deserialize)]
+struct LedgerSnapshotFile {
+    protocol_version: u32,
+    sequence_number: u32,
+    timestamp: u64,
+    #[serde(with = "hex_network_id")]
+    network_id: [u8; 32],
+    base_reserve: u32,
+    min_persistent_entry_ttl: u32,
+    min_temp_entry_ttl: u32,
+    max_entry_ttl: u32,
+    ledger_entries: Vec<(LedgerKey, (LedgerEntry, Option<u32>))>,
+}
+
+mod hex_network_id {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(network_id: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(network_id))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        let network_id = String::deserialize(deserializer)?;
+        hex::decode(&network_id)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| D::Error::custom(format!("invalid network id {network_id:?}")))
+    }
+}
+
+impl From<LedgerSnapshotFile> for LedgerSnapshot {
+    fn from(file: LedgerSnapshotFile) -> Self {
+        let mut snapshot = LedgerSnapshot {
+            protocol_version: file.protocol_version,
+            sequence_number: file.sequence_number,
+            timestamp: file.timestamp,
+            network_id: file.network_id,
+            base_reserve: file.base_reserve,
+            min_persistent_entry_ttl: file.min_persistent_entry_ttl,
+            min_temp_entry_ttl: file.min_temp_entry_ttl,
+            max_entry_ttl: file.max_entry_ttl,
+            ..Default::default()
+        };
+        snapshot.extend_entries(file.ledger_entries);
+
+        snapshot
+    }
+}
+
+impl From<LedgerSnapshot> for LedgerSnapshotFile {
+    fn from(snapshot: LedgerSnapshot) -> Self {
+        LedgerSnapshotFile {
+            protocol_version: snapshot.protocol_version,
+            sequence_number: snapshot.sequence_number,
+            timestamp: snapshot.timestamp,
+            network_id: snapshot.network_id,
+            base_reserve: snapshot.base_reserve,
+            min_persistent_entry_ttl: snapshot.min_persistent_entry_ttl,
+            min_temp_entry_ttl: snapshot.min_temp_entry_ttl,
+            max_entry_ttl: snapshot.max_entry_ttl,
+            ledger_entries: snapshot.entries(),
+        }
+    }
+}
+
+/// A snapshot file, either a bare ledger snapshot (`soroban snapshot`) or a
+/// contract test snapshot, which wraps it in `ledger`.
+#[derive(Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum SnapshotFile {
+    Test { ledger: LedgerSnapshot },
+    Ledger(LedgerSnapshot),
+}
+
+/// Picks the form from the shape of the file, so that a malformed file gets
+/// the error of the form it was meant as instead of matching neither.
+impl<'de> Deserialize<'de> for SnapshotFile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Object(mut file) if file.contains_key("ledger") => {
+                let ledger = file.remove("ledger").unwrap_or_default();
+                serde_json::from_value(ledger)
+                    .map(|ledger| Self::Test { ledger })
+                    .map_err(|e| D::Error::custom(format!("invalid test snapshot ledger: {e}")))
+            }
+            file => serde_json::from_value(file)
+                .map(Self::Ledger)
+                .map_err(|e| D::Error::custom(format!("invalid ledger snapshot: {e}"))),
+        }
+    }
+}
+
+impl SnapshotFile {
+    pub fn into_snapshot(self) -> LedgerSnapshot {
+        match self {
+            Self::Test { ledger } | Self::Ledger(ledger) => ledger,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(from = "LedgerSnapshotFile", into = "LedgerSnapshotFile")]
 pub struct LedgerSnapshot {
     pub protocol_version: u32,
     pub sequence_number: u32,
@@ -18,7 +137,58 @@ pub struct LedgerSnapshot {
     pub min_persistent_entry_ttl: u32,
     pub min_temp_entry_ttl: u32,
     pub max_entry_ttl: u32,
-    pub ledger_entries: Vec<(Box<LedgerKey>, (Box<LedgerEntry>, Option<u32>))>,
+    pub ledger_entries: LedgerEntries,
+    /// Last key the host asked for which wasn't in the snapshot.
+    pub missing_key: RefCell<Option<LedgerKey>>,
+    /// Archived persistent entries the host has accessed, in access order.
+    pub archived_keys: RefCell<Vec<LedgerKey>>,
+}
+
+/// Whether the entry behind `key` has a TTL at all. Only contract data and
+/// code expire, every other entry lives forever.
+pub fn has_ttl(key: &LedgerKey) -> bool {
+    matches!(key, LedgerKey::ContractCode(_) | LedgerKey::ContractData(_))
+}
+
+/// Whether an entry living until `live_until` can still be accessed at
+/// `ledger_sequence`.
+pub fn is_live(live_until: Option<u32>, ledger_sequence: u32) -> bool {
+    match live_until {
+        Some(live_until) => live_until >= ledger_sequence,
+        None => true,
+    }
+}
+
+/// Whether the entry behind `key` is archived (rather than deleted) once its
+/// TTL runs out.
+pub fn is_persistent(key: &LedgerKey) -> bool {
+    match key {
+        LedgerKey::ContractCode(_) => true,
+        LedgerKey::ContractData(data) => data.durability == ContractDataDurability::Persistent,
+        _ => false,
+    }
+}
+
+/// The key of a ledger entry soroban can read or write.
+pub fn ledger_key(entry: &LedgerEntry) -> Option<LedgerKey> {
+    match &entry.data {
+        LedgerEntryData::Account(account) => Some(LedgerKey::Account(LedgerKeyAccount {
+            account_id: account.account_id.clone(),
+        })),
+        LedgerEntryData::Trustline(trustline) => Some(LedgerKey::Trustline(LedgerKeyTrustLine {
+            account_id: trustline.account_id.clone(),
+            asset: trustline.asset.clone(),
+        })),
+        LedgerEntryData::ContractData(data) => Some(LedgerKey::ContractData(LedgerKeyContractData {
+            contract: data.contract.clone(),
+            key: data.key.clone(),
+            durability: data.durability,
+        })),
+        LedgerEntryData::ContractCode(code) => Some(LedgerKey::ContractCode(LedgerKeyContractCode {
+            hash: code.hash.clone(),
+        })),
+        _ => None,
+    }
 }
 
 impl LedgerSnapshot {
@@ -34,6 +204,138 @@ impl LedgerSnapshot {
             max_entry_ttl: self.max_entry_ttl,
         }
     }
+
+    /// Inserts an entry. When the key is already present its entry is
+    /// replaced (last write wins) but it keeps its original position.
+    pub fn insert(&mut self, key: LedgerKey, entry: LedgerEntry, live_until: Option<u32>) {
+        self.ledger_entries.insert(key, (Box::new(entry), live_until));
+    }
+
+    pub fn extend_entries(
+        &mut self,
+        entries: impl IntoIterator<Item = (LedgerKey, (LedgerEntry, Option<u32>))>,
+    ) {
+        for (key, (entry, live_until)) in entries {
+            self.insert(key, entry, live_until);
+        }
+    }
+
+    /// The entries in insertion order, in the same shape as the invocation
+    /// params' `ledger_entries`.
+    pub fn entries(&self) -> Vec<(LedgerKey, (LedgerEntry, Option<u32>))> {
+        self.ledger_entries
+            .iter()
+            .map(|(key, (entry, live_until))| (key.clone(), (*entry.clone(), *live_until)))
+            .collect()
+    }
+
+    /// A snapshot with the same entries but a different ledger header, and
+    /// without any access tracking state.
+    pub fn with_header_of(&self, header: &LedgerSnapshot) -> LedgerSnapshot {
+        LedgerSnapshot {
+            ledger_entries: self.ledger_entries.clone(),
+            missing_key: RefCell::default(),
+            archived_keys: RefCell::default(),
+            ..header.clone()
+        }
+    }
+
+    /// Folds the ledger changes of a simulation back into the snapshot, then
+    /// its TTL changes, which cover both written and extended entries.
+    /// Written entries without a TTL change keep their TTL, or get the minimum
+    /// TTL for their durability if they were created or archived.
+    pub fn apply_changes(&mut self, changes: &[LedgerEntryDiff], ttl_changes: &[TtlChange]) {
+        for change in changes {
+            let Some(key) = change
+                .state_after
+                .as_ref()
+                .or(change.state_before.as_ref())
+                .and_then(ledger_key)
+            else {
+                continue;
+            };
+
+            let Some(entry) = &change.state_after else {
+                self.ledger_entries.shift_remove(&key);
+                continue;
+            };
+
+            let live_until = match self.ledger_entries.get(&key) {
+                Some((_, live_until)) if !self.is_expired(&key, *live_until) => *live_until,
+                _ => self.fresh_live_until(&key),
+            };
+            self.insert(key, entry.clone(), live_until);
+        }
+
+        for change in ttl_changes {
+            if let Some((_, live_until)) = self.ledger_entries.get_mut(&change.key) {
+                *live_until = Some(change.new_live_until_ledger);
+            }
+        }
+    }
+
+    fn fresh_live_until(&self, key: &LedgerKey) -> Option<u32> {
+        if !has_ttl(key) {
+            return None;
+        }
+
+        let min_ttl = if is_persistent(key) {
+            self.min_persistent_entry_ttl
+        } else {
+            self.min_temp_entry_ttl
+        };
+
+        Some(self.sequence_number.saturating_add(min_ttl.saturating_sub(1)))
+    }
+
+    /// The WASM code of `contract`, found through its instance entry.
+    pub fn contract_wasm(&self, contract: &[u8; 32]) -> Option<Vec<u8>> {
+        let instance_key = LedgerKey::ContractData(LedgerKeyContractData {
+            contract: ScAddress::Contract(Hash(*contract)),
+            key: ScVal::LedgerKeyContractInstance,
+            durability: ContractDataDurability::Persistent,
+        });
+
+        let LedgerEntryData::ContractData(data) = &self.ledger_entries.get(&instance_key)?.0.data else {
+            return None;
+        };
+        let ScVal::ContractInstance(instance) = &data.val else {
+            return None;
+        };
+        let ContractExecutable::Wasm(hash) = &instance.executable else {
+            return None;
+        };
+
+        let code_key = LedgerKey::ContractCode(LedgerKeyContractCode { hash: hash.clone() });
+        match &self.ledger_entries.get(&code_key)?.0.data {
+            LedgerEntryData::ContractCode(code) => Some(code.code.to_vec()),
+            _ => None,
+        }
+    }
+
+    /// A view serving the entries with their stored TTL, for simulating a
+    /// restore of the archived entries.
+    pub fn archive_view(&self) -> ArchiveView<'_> {
+        ArchiveView(self)
+    }
+
+    pub fn take_missing_key(&self) -> Option<LedgerKey> {
+        self.missing_key.borrow_mut().take()
+    }
+
+    /// Keys of the archived entries accessed so far, which need to be restored
+    /// before the invocation can be submitted to a network.
+    pub fn archived_keys(&self) -> Vec<LedgerKey> {
+        self.archived_keys.borrow().clone()
+    }
+
+    fn is_expired(&self, key: &LedgerKey, live_until: Option<u32>) -> bool {
+        has_ttl(key) && !is_live(live_until, self.sequence_number)
+    }
+
+    fn record_missing(&self, key: &LedgerKey) {
+        *self.missing_key.borrow_mut() = Some(key.clone());
+    }
 }
 
 impl SnapshotSource for LedgerSnapshot {
@@ -42,13 +344,45 @@ impl SnapshotSource for LedgerSnapshot {
         key: &std::rc::Rc<LedgerKey>,
     ) -> Result<Option<soroban_env_host::storage::EntryWithLiveUntil>, soroban_env_host::HostError>
     {
-        match self.ledger_entries.iter().find(|(k, _)| **k == **key) {
-            Some((_, v)) => Ok(Some((Rc::new(*v.0.clone()), v.1))),
-            None => Err(ScError::Storage(ScErrorCode::MissingValue).into()),
+        match self.ledger_entries.get(key.as_ref()) {
+            Some(v) if !self.is_expired(key, v.1) => Ok(Some((Rc::new(*v.0.clone()), v.1))),
+            Some(v) if is_persistent(key) => {
+                // Like soroban-rpc, archived entries are served as if they were just
+                // restored, and the caller gets a restore preamble for them.
+                let mut archived_keys = self.archived_keys.borrow_mut();
+                if !archived_keys.contains(&**key) {
+                    archived_keys.push((**key).clone());
+                }
+
+                Ok(Some((Rc::new(*v.0.clone()), self.fresh_live_until(key))))
+            }
+            // Expired temporary entries are deleted from the ledger.
+            _ => {
+                self.record_missing(key);
+                Ok(None)
+            }
         }
     }
 }
 
+/// Serves the entries of a snapshot as stored. Unlike the snapshot itself, it
+/// returns archived entries with their expired `live_until`, which is what
+/// tells the restore simulation they need restoring, and tracks no accesses.
+pub struct ArchiveView<'a>(&'a LedgerSnapshot);
+
+impl SnapshotSource for ArchiveView<'_> {
+    fn get(
+        &self,
+        key: &std::rc::Rc<LedgerKey>,
+    ) -> Result<Option<soroban_env_host::storage::EntryWithLiveUntil>, soroban_env_host::HostError>
+    {
+        Ok(self
+            .0
+            .ledger_entries
+            .get(key.as_ref())
+            .map(|(entry, live_until)| (Rc::new(*entry.clone()), *live_until)))
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EntryWithLifetime {
@@ -58,11 +392,7 @@ pub struct EntryWithLifetime {
 
 impl EntryWithLifetime {
     pub fn is_live(&self, ledger_sequence: u32) -> bool {
-        if let Some(ttl) = self.live_until {
-            ledger_sequence < ttl
-        } else {
-            true // all other entries are always live
-        }
+        is_live(self.live_until, ledger_sequence)
     }
 }
 
@@ -75,25 +405,202 @@ pub fn ledger_snapshot_from_entries_and_ledger(
     // Using a custom network id isn't really required at this point, but keeping it
     // to distinguish from other real networks. 
     let network_id = network.unwrap_or("Soroflare Stellar Network ; March 2024");
-    let mut ledger_entries = Vec::new();
-
-    for (idx, key) in keys.iter().enumerate() {
-        let entry_with_lifetime = &vals[idx];
-        ledger_entries.push((
-            Box::new(key.clone()),
-            (
-                Box::new(entry_with_lifetime.entry.clone()),
-                entry_with_lifetime.live_until,
-            ),
-        ))
-    }
-
-    LedgerSnapshot {
+    let mut snapshot = LedgerSnapshot {
         network_id: hashed_network_id(network_id),
         sequence_number: ledger_sequence,
-        ledger_entries,
         protocol_version: 20,
         ..Default::default()
+    };
+
+    for (key, entry_with_lifetime) in keys.into_iter().zip(vals) {
+        snapshot.insert(key, entry_with_lifetime.entry, entry_with_lifetime.live_until);
     }
+
+    snapshot
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutils::{contract_data, persistent_data};
+
+    #[test]
+    fn duplicate_keys_last_write_wins() {
+        let mut snapshot = LedgerSnapshot::default();
+        let (key_a, first) = persistent_data(1, 1);
+        let (key_b, other) = persistent_data(2, 2);
+        let (_, second) = persistent_data(1, 3);
+
+        snapshot.insert(key_a.clone(), first, Some(10));
+        snapshot.insert(key_b.clone(), other, Some(10));
+        snapshot.insert(key_a.clone(), second.clone(), Some(20));
+
+        let keys: Vec<&LedgerKey> = snapshot.ledger_entries.keys().collect();
+        assert_eq!(keys, vec![&key_a, &key_b]);
+
+        let (entry, live_until) = snapshot.get(&Rc::new(key_a)).unwrap().unwrap();
+        assert_eq!(*entry, second);
+        assert_eq!(live_until, Some(20));
+    }
+
+    #[test]
+    fn expired_entries_follow_network_semantics() {
+        let mut snapshot = LedgerSnapshot {
+            sequence_number: 100,
+            min_persistent_entry_ttl: 10,
+            ..Default::default()
+        };
+        let (temp_key, temp) = contract_data(1, 1, ContractDataDurability::Temporary);
+        let (persistent_key, persistent) = persistent_data(2, 2);
+        let (live_key, live) = persistent_data(3, 3);
+
+        snapshot.insert(temp_key.clone(), temp, Some(99));
+        snapshot.insert(persistent_key.clone(), persistent.clone(), Some(99));
+        snapshot.insert(live_key.clone(), live, Some(100));
+
+        assert!(snapshot.get(&Rc::new(temp_key.clone())).unwrap().is_none());
+        assert_eq!(snapshot.take_missing_key(), Some(temp_key));
+
+        let (entry, live_until) = snapshot.get(&Rc::new(persistent_key.clone())).unwrap().unwrap();
+        assert_eq!(*entry, persistent);
+        assert_eq!(live_until, Some(109));
+        assert_eq!(snapshot.archived_keys(), vec![persistent_key]);
+
+        assert!(snapshot.get(&Rc::new(live_key)).unwrap().is_some());
+        assert_eq!(snapshot.archived_keys().len(), 1);
+    }
+
+    #[test]
+    fn apply_changes_updates_creates_and_deletes() {
+        let mut snapshot = LedgerSnapshot {
+            sequence_number: 100,
+            min_persistent_entry_ttl: 10,
+            min_temp_entry_ttl: 5,
+            ..Default::default()
+        };
+        let (updated_key, before) = persistent_data(1, 1);
+        let (_, after) = persistent_data(1, 2);
+        let (deleted_key, deleted) = persistent_data(2, 2);
+        let (created_key, created) = contract_data(3, 3, ContractDataDurability::Temporary);
+        let (extended_key, extended) = persistent_data(4, 4);
+
+        snapshot.insert(updated_key.clone(), before.clone(), Some(500));
+        snapshot.insert(deleted_key.clone(), deleted.clone(), Some(500));
+        snapshot.insert(extended_key.clone(), extended.clone(), Some(500));
+
+        snapshot.apply_changes(&[
+            LedgerEntryDiff {
+                state_before: Some(before),
+                state_after: Some(after.clone()),
+            },
+            LedgerEntryDiff {
+                state_before: Some(deleted),
+                state_after: None,
+            },
+            LedgerEntryDiff {
+                state_before: None,
+                state_after: Some(created.clone()),
+            },
+        ], &[TtlChange {
+            key: extended_key.clone(),
+            old_live_until_ledger: 500,
+            new_live_until_ledger: 1000,
+        }]);
+
+        assert_eq!(
+            snapshot.entries(),
+            vec![
+                (updated_key, (after, Some(500))),
+                (extended_key, (extended, Some(1000))),
+                (created_key, (created, Some(104))),
+            ]
+        );
+    }
+
+    /// A ledger snapshot as written by `soroban snapshot` and the SDK, with
+    /// one contract data entry of [`persistent_data`].
+    const LEDGER_SNAPSHOT: &str = r#"{
+        "protocol_version": 20,
+        "sequence_number": 1000,
+        "timestamp": 1700000000,
+        "network_id": "cee0302d59844d32bdca915c8203dd44b33fbb7edc19051ea37abedf28ecd472",
+        "base_reserve": 5000000,
+        "min_persistent_entry_ttl": 4096,
+        "min_temp_entry_ttl": 16,
+        "max_entry_ttl": 6312000,
+        "ledger_entries": [
+            [
+                {
+                    "contract_data": {
+                        "contract": { "contract": "0000000000000000000000000000000000000000000000000000000000000000" },
+                        "key": { "u32": 1 },
+                        "durability": "persistent"
+                    }
+                },
+                [
+                    {
+                        "last_modified_ledger_seq": 0,
+                        "data": {
+                            "contract_data": {
+                                "ext": "v0",
+                                "contract": { "contract": "0000000000000000000000000000000000000000000000000000000000000000" },
+                                "key": { "u32": 1 },
+                                "durability": "persistent",
+                                "val": { "u32": 2 }
+                            }
+                        },
+                        "ext": "v0"
+                    },
+                    5095
+                ]
+            ]
+        ]
+    }"#;
+
+    fn assert_ledger_snapshot(snapshot: &LedgerSnapshot) {
+        assert_eq!(snapshot.protocol_version, 20);
+        assert_eq!(snapshot.sequence_number, 1000);
+        assert_eq!(snapshot.network_id, hashed_network_id("Test SDF Network ; September 2015"));
+        assert_eq!(snapshot.max_entry_ttl, 6312000);
+
+        let (key, entry) = persistent_data(1, 2);
+        assert_eq!(snapshot.entries(), vec![(key, (entry, Some(5095)))]);
+    }
+
+    #[test]
+    fn reads_and_writes_cli_snapshots() {
+        let file: SnapshotFile = serde_json::from_str(LEDGER_SNAPSHOT).unwrap();
+        assert!(matches!(file, SnapshotFile::Ledger(_)));
+        let written = serde_json::to_value(&file).unwrap();
+        assert_ledger_snapshot(&file.into_snapshot());
+
+        let original: Value = serde_json::from_str(LEDGER_SNAPSHOT).unwrap();
+        assert_eq!(written, original);
+    }
+
+    #[test]
+    fn reads_and_writes_contract_test_snapshots() {
+        let test_snapshot = format!(
+            r#"{{ "generators": {{ "address": 1, "nonce": 0 }}, "auth": [[]], "ledger": {LEDGER_SNAPSHOT}, "events": [] }}"#
+        );
+
+        let file: SnapshotFile = serde_json::from_str(&test_snapshot).unwrap();
+        assert!(matches!(file, SnapshotFile::Test { .. }));
+        let written = serde_json::to_value(&file).unwrap();
+        assert_ledger_snapshot(&file.into_snapshot());
+
+        let ledger: Value = serde_json::from_str(LEDGER_SNAPSHOT).unwrap();
+        assert_eq!(written, serde_json::json!({ "ledger": ledger }));
+    }
+
+    #[test]
+    fn malformed_snapshot_files_name_the_problem() {
+        let bad_network_id = LEDGER_SNAPSHOT.replace("cee0302d", "cee0");
+        let error = serde_json::from_str::<SnapshotFile>(&bad_network_id).unwrap_err().to_string();
+        assert!(error.contains("invalid ledger snapshot: invalid network id"), "{error}");
+
+        let missing_sequence = format!(r#"{{ "ledger": {} }}"#, LEDGER_SNAPSHOT.replace(r#""sequence_number": 1000,"#, ""));
+        let error = serde_json::from_str::<SnapshotFile>(&missing_sequence).unwrap_err().to_string();
+        assert!(error.contains("invalid test snapshot ledger: missing field `sequence_number`"), "{error}");
+    }
+}
@@ -0,0 +1,497 @@
+//! Contract spec parsing, and conversion of plain JSON arguments into `ScVal`s
+//! typed after a function's spec.
+
+use std::io::Cursor;
+
+use serde_json::{Map, Value};
+use soroban_env_host::xdr::{
+    AccountId, Hash, Int128Parts, Int256Parts, Limited, Limits, PublicKey, ReadXdr, ScAddress,
+    ScBytes, ScError, ScMap, ScMapEntry, ScSpecEntry, ScSpecFunctionV0, ScSpecTypeDef,
+    ScSpecUdtUnionCaseV0, ScString, ScSymbol, ScVal, ScVec, UInt128Parts, UInt256Parts, Uint256,
+};
+
+use crate::{
+    native::{negate_limbs, parse_u256},
+    wasm, SoroflareError,
+};
+
+pub const SPEC_SECTION: &str = "contractspecv0";
+
+/// Reads a sequence of XDR values laid out back to back, as in the contract
+/// spec and meta sections.
+pub(crate) fn read_xdr_stream<T: ReadXdr>(bytes: &[u8]) -> Result<Vec<T>, SoroflareError> {
+    let mut limited = Limited::new(Cursor::new(bytes), Limits::none());
+    let mut entries = Vec::new();
+
+    while (limited.inner.position() as usize) < bytes.len() {
+        entries.push(
+            T::read_xdr(&mut limited)
+                .map_err(|e| SoroflareError::InvalidParams(format!("invalid xdr in wasm section: {e}")))?,
+        );
+    }
+
+    Ok(entries)
+}
+
+fn type_name(ty: &ScSpecTypeDef) -> String {
+    match ty {
+        ScSpecTypeDef::Option(option) => format!("option<{}>", type_name(&option.value_type)),
+        ScSpecTypeDef::Vec(vec) => format!("vec<{}>", type_name(&vec.element_type)),
+        ScSpecTypeDef::Map(map) => format!("map<{}, {}>", type_name(&map.key_type), type_name(&map.value_type)),
+        ScSpecTypeDef::BytesN(bytes) => format!("bytes<{}>", bytes.n),
+        ScSpecTypeDef::Udt(udt) => udt.name.to_utf8_string_lossy(),
+        other => other.name().to_lowercase(),
+    }
+}
+
+fn expected(ty: &str, value: &Value) -> String {
+    format!("expected {ty}, got {value}")
+}
+
+/// Numbers may be given as JSON numbers or as decimal strings.
+fn as_decimal(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(number) => Some(number.to_string()),
+        Value::String(string) => Some(string.clone()),
+        _ => None,
+    }
+}
+
+fn parse_i256(decimal: &str) -> Option<[u64; 4]> {
+    match decimal.strip_prefix('-') {
+        Some(magnitude) => {
+            let limbs = negate_limbs(parse_u256(magnitude)?);
+            (limbs[0] >> 63 == 1 || limbs == [0; 4]).then_some(limbs)
+        }
+        None => parse_u256(decimal).filter(|limbs| limbs[0] >> 63 == 0),
+    }
+}
+
+fn parse_address(address: &str) -> Option<ScAddress> {
+    if let Ok(account) = stellar_strkey::ed25519::PublicKey::from_string(address) {
+        return Some(ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(account.0)))));
+    }
+
+    stellar_strkey::Contract::from_string(address)
+        .ok()
+        .map(|contract| ScAddress::Contract(Hash(contract.0)))
+}
+
+fn sorted_map(mut entries: Vec<ScMapEntry>) -> Result<ScVal, String> {
+    // The host only accepts maps with sorted keys.
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(ScVal::Map(Some(ScMap(entries.try_into().map_err(|_| "map is too large".to_string())?))))
+}
+
+fn vec(items: Vec<ScVal>) -> Result<ScVal, String> {
+    Ok(ScVal::Vec(Some(ScVec(items.try_into().map_err(|_| "vec is too large".to_string())?))))
+}
+
+fn symbol(name: &str) -> Result<ScVal, String> {
+    Ok(ScVal::Symbol(ScSymbol(name.try_into().map_err(|_| format!("invalid symbol {name:?}"))?)))
+}
+
+/// The spec entries of a contract, read from its `contractspecv0` section.
+pub struct ContractSpec {
+    pub entries: Vec<ScSpecEntry>,
+}
+
+impl ContractSpec {
+    pub fn from_wasm(wasm: &[u8]) -> Result<Self, SoroflareError> {
+        let section = wasm::custom_section(wasm, SPEC_SECTION)?.ok_or_else(|| {
+            SoroflareError::InvalidParams(format!("contract has no {SPEC_SECTION} section"))
+        })?;
+
+        Ok(Self {
+            entries: read_xdr_stream(section)?,
+        })
+    }
+
+    pub fn function(&self, name: &str) -> Option<&ScSpecFunctionV0> {
+        self.entries.iter().find_map(|entry| match entry {
+            ScSpecEntry::FunctionV0(function) if function.name.0.to_utf8_string_lossy() == name => {
+                Some(function)
+            }
+            _ => None,
+        })
+    }
+
+    fn udt(&self, name: &str) -> Option<&ScSpecEntry> {
+        self.entries.iter().find(|entry| match entry {
+            ScSpecEntry::UdtStructV0(udt) => udt.name.to_utf8_string_lossy() == name,
+            ScSpecEntry::UdtUnionV0(udt) => udt.name.to_utf8_string_lossy() == name,
+            ScSpecEntry::UdtEnumV0(udt) => udt.name.to_utf8_string_lossy() == name,
+            ScSpecEntry::UdtErrorEnumV0(udt) => udt.name.to_utf8_string_lossy() == name,
+            ScSpecEntry::FunctionV0(_) => false,
+        })
+    }
+
+    /// Converts named JSON arguments into the arguments of `fname`, in the
+    /// order of its spec.
+    pub fn args_from_json(&self, fname: &str, args: &Map<String, Value>) -> Result<Vec<ScVal>, SoroflareError> {
+        let function = self
+            .function(fname)
+            .ok_or_else(|| SoroflareError::InvalidParams(format!("contract has no function `{fname}`")))?;
+        let names: Vec<String> = function
+            .inputs
+            .iter()
+            .map(|input| input.name.to_utf8_string_lossy())
+            .collect();
+
+        if let Some(unknown) = args.keys().find(|key| !names.contains(key)) {
+            return Err(SoroflareError::InvalidParams(format!(
+                "function `{fname}` has no argument `{unknown}`"
+            )));
+        }
+
+        function
+            .inputs
+            .iter()
+            .zip(names)
+            .map(|(input, name)| {
+                let value = match (args.get(&name), &input.type_) {
+                    (Some(value), _) => value,
+                    (None, ScSpecTypeDef::Option(_)) => &Value::Null,
+                    (None, _) => {
+                        return Err(SoroflareError::InvalidParams(format!("missing argument `{name}`")))
+                    }
+                };
+
+                self.from_json(&input.type_, value)
+                    .map_err(|reason| SoroflareError::InvalidParams(format!("argument `{name}`: {reason}")))
+            })
+            .collect()
+    }
+
+    /// Converts a JSON value into an `ScVal` of type `ty`.
+    pub fn from_json(&self, ty: &ScSpecTypeDef, value: &Value) -> Result<ScVal, String> {
+        let mismatch = || expected(&type_name(ty), value);
+
+        match ty {
+            ScSpecTypeDef::Val => serde_json::from_value(value.clone()).map_err(|e| e.to_string()),
+            ScSpecTypeDef::Bool => value.as_bool().map(ScVal::Bool).ok_or_else(mismatch),
+            ScSpecTypeDef::Void => value.is_null().then_some(ScVal::Void).ok_or_else(mismatch),
+            ScSpecTypeDef::Error => value
+                .as_u64()
+                .and_then(|code| code.try_into().ok())
+                .map(|code| ScVal::Error(ScError::Contract(code)))
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::U32 => value
+                .as_u64()
+                .and_then(|n| n.try_into().ok())
+                .map(ScVal::U32)
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::I32 => value
+                .as_i64()
+                .and_then(|n| n.try_into().ok())
+                .map(ScVal::I32)
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::U64 => as_decimal(value)
+                .and_then(|n| n.parse().ok())
+                .map(ScVal::U64)
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::I64 => as_decimal(value)
+                .and_then(|n| n.parse().ok())
+                .map(ScVal::I64)
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::Timepoint => as_decimal(value)
+                .and_then(|n| n.parse().ok())
+                .map(|n| ScVal::Timepoint(n.into()))
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::Duration => as_decimal(value)
+                .and_then(|n| n.parse().ok())
+                .map(|n| ScVal::Duration(n.into()))
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::U128 => as_decimal(value)
+                .and_then(|n| n.parse::<u128>().ok())
+                .map(|n| {
+                    ScVal::U128(UInt128Parts {
+                        hi: (n >> 64) as u64,
+                        lo: n as u64,
+                    })
+                })
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::I128 => as_decimal(value)
+                .and_then(|n| n.parse::<i128>().ok())
+                .map(|n| {
+                    ScVal::I128(Int128Parts {
+                        hi: (n >> 64) as i64,
+                        lo: n as u64,
+                    })
+                })
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::U256 => as_decimal(value)
+                .and_then(|n| parse_u256(&n))
+                .map(|[hi_hi, hi_lo, lo_hi, lo_lo]| {
+                    ScVal::U256(UInt256Parts {
+                        hi_hi,
+                        hi_lo,
+                        lo_hi,
+                        lo_lo,
+                    })
+                })
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::I256 => as_decimal(value)
+                .and_then(|n| parse_i256(&n))
+                .map(|[hi_hi, hi_lo, lo_hi, lo_lo]| {
+                    ScVal::I256(Int256Parts {
+                        hi_hi: hi_hi as i64,
+                        hi_lo,
+                        lo_hi,
+                        lo_lo,
+                    })
+                })
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::Bytes => value
+                .as_str()
+                .and_then(|bytes| hex::decode(bytes).ok())
+                .and_then(|bytes| bytes.try_into().ok())
+                .map(|bytes| ScVal::Bytes(ScBytes(bytes)))
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::BytesN(bytes_n) => value
+                .as_str()
+                .and_then(|bytes| hex::decode(bytes).ok())
+                .filter(|bytes| bytes.len() == bytes_n.n as usize)
+                .and_then(|bytes| bytes.try_into().ok())
+                .map(|bytes| ScVal::Bytes(ScBytes(bytes)))
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::String => value
+                .as_str()
+                .and_then(|string| string.try_into().ok())
+                .map(|string| ScVal::String(ScString(string)))
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::Symbol => value.as_str().ok_or_else(mismatch).and_then(symbol),
+            ScSpecTypeDef::Address => value
+                .as_str()
+                .and_then(parse_address)
+                .map(ScVal::Address)
+                .ok_or_else(mismatch),
+            ScSpecTypeDef::Option(option) => match value {
+                Value::Null => Ok(ScVal::Void),
+                value => self.from_json(&option.value_type, value),
+            },
+            ScSpecTypeDef::Result(_) => Err("results can't be used as arguments".into()),
+            ScSpecTypeDef::Vec(vec_type) => {
+                let items = value.as_array().ok_or_else(mismatch)?;
+                vec(items
+                    .iter()
+                    .map(|item| self.from_json(&vec_type.element_type, item))
+                    .collect::<Result<_, _>>()?)
+            }
+            ScSpecTypeDef::Map(map_type) => {
+                let entries = match value {
+                    Value::Object(object) => object
+                        .iter()
+                        .map(|(key, val)| {
+                            Ok(ScMapEntry {
+                                key: self.from_json(&map_type.key_type, &Value::String(key.clone()))?,
+                                val: self.from_json(&map_type.value_type, val)?,
+                            })
+                        })
+                        .collect::<Result<_, String>>()?,
+                    Value::Array(pairs) => pairs
+                        .iter()
+                        .map(|pair| match pair.as_array().map(Vec::as_slice) {
+                            Some([key, val]) => Ok(ScMapEntry {
+                                key: self.from_json(&map_type.key_type, key)?,
+                                val: self.from_json(&map_type.value_type, val)?,
+                            }),
+                            _ => Err(expected("[key, value] pair", pair)),
+                        })
+                        .collect::<Result<_, String>>()?,
+                    _ => return Err(mismatch()),
+                };
+
+                sorted_map(entries)
+            }
+            ScSpecTypeDef::Tuple(tuple) => {
+                let items = value
+                    .as_array()
+                    .filter(|items| items.len() == tuple.value_types.len())
+                    .ok_or_else(mismatch)?;
+
+                vec(tuple
+                    .value_types
+                    .iter()
+                    .zip(items)
+                    .map(|(ty, item)| self.from_json(ty, item))
+                    .collect::<Result<_, _>>()?)
+            }
+            ScSpecTypeDef::Udt(udt) => {
+                let name = udt.name.to_utf8_string_lossy();
+                let entry = self.udt(&name).ok_or_else(|| format!("unknown type `{name}`"))?;
+
+                self.udt_from_json(entry, value).map_err(|reason| format!("{name}: {reason}"))
+            }
+        }
+    }
+
+    fn udt_from_json(&self, entry: &ScSpecEntry, value: &Value) -> Result<ScVal, String> {
+        match entry {
+            // Tuple structs have numbered fields and are represented as vecs.
+            ScSpecEntry::UdtStructV0(udt)
+                if udt.fields.iter().all(|field| field.name.to_utf8_string_lossy().parse::<u32>().is_ok()) =>
+            {
+                let items = value
+                    .as_array()
+                    .filter(|items| items.len() == udt.fields.len())
+                    .ok_or_else(|| expected("tuple struct", value))?;
+
+                vec(udt
+                    .fields
+                    .iter()
+                    .zip(items)
+                    .map(|(field, item)| self.from_json(&field.type_, item))
+                    .collect::<Result<_, _>>()?)
+            }
+            ScSpecEntry::UdtStructV0(udt) => {
+                let object = value.as_object().ok_or_else(|| expected("struct", value))?;
+
+                let entries = udt
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let name = field.name.to_utf8_string_lossy();
+                        let val = object
+                            .get(&name)
+                            .ok_or_else(|| format!("missing field `{name}`"))?;
+
+                        Ok(ScMapEntry {
+                            key: symbol(&name)?,
+                            val: self
+                                .from_json(&field.type_, val)
+                                .map_err(|reason| format!("field `{name}`: {reason}"))?,
+                        })
+                    })
+                    .collect::<Result<_, String>>()?;
+
+                sorted_map(entries)
+            }
+            // Unions are given as `"Case"` or `{"Case": [values]}`.
+            ScSpecEntry::UdtUnionV0(udt) => {
+                let (case_name, values) = match value {
+                    Value::String(case_name) => (case_name.as_str(), None),
+                    Value::Object(object) if object.len() == 1 => {
+                        let (case_name, values) = object.iter().next().unwrap();
+                        (case_name.as_str(), Some(values))
+                    }
+                    _ => return Err(expected("union case", value)),
+                };
+
+                let case = udt
+                    .cases
+                    .iter()
+                    .find(|case| match case {
+                        ScSpecUdtUnionCaseV0::VoidV0(case) => case.name.to_utf8_string_lossy() == case_name,
+                        ScSpecUdtUnionCaseV0::TupleV0(case) => case.name.to_utf8_string_lossy() == case_name,
+                    })
+                    .ok_or_else(|| format!("unknown case `{case_name}`"))?;
+
+                let mut items = vec![symbol(case_name)?];
+                match (case, values) {
+                    (ScSpecUdtUnionCaseV0::VoidV0(_), None) => {}
+                    (ScSpecUdtUnionCaseV0::TupleV0(case), Some(values)) => {
+                        // A single value doesn't need to be wrapped in a list.
+                        let values = match values {
+                            Value::Array(values) if case.type_.len() != 1 || values.len() == 1 => values.clone(),
+                            value => vec![value.clone()],
+                        };
+                        if values.len() != case.type_.len() {
+                            return Err(format!("case `{case_name}` takes {} values", case.type_.len()));
+                        }
+
+                        for (ty, value) in case.type_.iter().zip(&values) {
+                            items.push(self.from_json(ty, value)?);
+                        }
+                    }
+                    (ScSpecUdtUnionCaseV0::VoidV0(_), Some(_)) => {
+                        return Err(format!("case `{case_name}` takes no values"))
+                    }
+                    (ScSpecUdtUnionCaseV0::TupleV0(_), None) => {
+                        return Err(format!("case `{case_name}` takes values"))
+                    }
+                }
+
+                vec(items)
+            }
+            ScSpecEntry::UdtEnumV0(udt) => {
+                let case = udt.cases.iter().find(|case| match value {
+                    Value::String(name) => case.name.to_utf8_string_lossy() == *name,
+                    value => value.as_u64() == Some(case.value as u64),
+                });
+
+                case.map(|case| ScVal::U32(case.value))
+                    .ok_or_else(|| expected("enum case", value))
+            }
+            ScSpecEntry::UdtErrorEnumV0(udt) => {
+                let case = udt.cases.iter().find(|case| match value {
+                    Value::String(name) => case.name.to_utf8_string_lossy() == *name,
+                    value => value.as_u64() == Some(case.value as u64),
+                });
+
+                case.map(|case| ScVal::Error(ScError::Contract(case.value)))
+                    .ok_or_else(|| expected("error case", value))
+            }
+            ScSpecEntry::FunctionV0(_) => Err("functions can't be used as arguments".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use soroban_env_host::xdr::{ScSpecFunctionInputV0, ScSymbol};
+
+    use super::*;
+
+    fn transfer_spec() -> ContractSpec {
+        let input = |name: &str, type_| ScSpecFunctionInputV0 {
+            doc: Default::default(),
+            name: name.try_into().unwrap(),
+            type_,
+        };
+
+        ContractSpec {
+            entries: vec![ScSpecEntry::FunctionV0(ScSpecFunctionV0 {
+                doc: Default::default(),
+                name: ScSymbol("transfer".try_into().unwrap()),
+                inputs: vec![
+                    input("to", ScSpecTypeDef::Address),
+                    input("amount", ScSpecTypeDef::I128),
+                ]
+                .try_into()
+                .unwrap(),
+                outputs: Default::default(),
+            })],
+        }
+    }
+
+    #[test]
+    fn named_args_follow_the_spec_order() {
+        let args = json!({
+            "amount": "-1000",
+            "to": "CAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABSC4",
+        });
+        let args = transfer_spec()
+            .args_from_json("transfer", args.as_object().unwrap())
+            .unwrap();
+
+        assert_eq!(
+            args,
+            vec![
+                ScVal::Address(ScAddress::Contract(Hash([0; 32]))),
+                ScVal::I128(Int128Parts { hi: -1, lo: u64::MAX - 999 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_name_the_argument() {
+        let args = json!({ "to": "nope", "amount": 1 });
+        let err = transfer_spec()
+            .args_from_json("transfer", args.as_object().unwrap())
+            .unwrap_err();
+
+        assert!(err.to_string().contains("argument `to`"), "{err}");
+    }
+}
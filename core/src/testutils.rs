@@ -0,0 +1,95 @@
+//! Fixtures shared by the tests and benches.
+
+use sha2::{Digest, Sha256};
+use soroban_env_host::xdr::{
+    Asset, ContractDataDurability, ContractDataEntry, ContractIdPreimage, ExtensionPoint, Hash, HashIdPreimage,
+    HashIdPreimageContractId, LedgerEntry, LedgerEntryData, LedgerEntryExt, LedgerKey, LedgerKeyContractData, Limits,
+    ScAddress, ScVal, WriteXdr,
+};
+use soroban_simulation::NetworkConfig;
+
+use crate::{
+    snapshot::{hashed_network_id, LedgerSnapshot},
+    HostFunctionParams, SoroflareInvocationParams, DEFAULT_BASE_RESERVE, DEFAULT_MAX_ENTRY_TTL,
+    DEFAULT_MIN_PERSISTENT_ENTRY_TTL, DEFAULT_MIN_TEMP_ENTRY_TTL, DEFAULT_PROTOCOL_VERSION,
+};
+
+pub const NETWORK: &str = "Soroflare Test Network";
+
+/// A contract data entry of the all zero contract, holding `val` under `key`.
+pub fn contract_data(key: u32, val: u32, durability: ContractDataDurability) -> (LedgerKey, LedgerEntry) {
+    let contract = ScAddress::Contract(Hash([0; 32]));
+    let ledger_key = LedgerKey::ContractData(LedgerKeyContractData {
+        contract: contract.clone(),
+        key: ScVal::U32(key),
+        durability,
+    });
+    let entry = LedgerEntry {
+        last_modified_ledger_seq: 0,
+        data: LedgerEntryData::ContractData(ContractDataEntry {
+            ext: ExtensionPoint::V0,
+            contract,
+            key: ScVal::U32(key),
+            durability,
+            val: ScVal::U32(val),
+        }),
+        ext: LedgerEntryExt::V0,
+    };
+
+    (ledger_key, entry)
+}
+
+pub fn persistent_data(key: u32, val: u32) -> (LedgerKey, LedgerEntry) {
+    contract_data(key, val, ContractDataDurability::Persistent)
+}
+
+/// An empty ledger on [`NETWORK`] with the default protocol, reserve and TTL
+/// limits.
+pub fn ledger_snapshot(sequence_number: u32) -> LedgerSnapshot {
+    LedgerSnapshot {
+        protocol_version: DEFAULT_PROTOCOL_VERSION,
+        sequence_number,
+        timestamp: 1_700_000_000,
+        network_id: hashed_network_id(NETWORK),
+        base_reserve: DEFAULT_BASE_RESERVE,
+        min_persistent_entry_ttl: DEFAULT_MIN_PERSISTENT_ENTRY_TTL,
+        min_temp_entry_ttl: DEFAULT_MIN_TEMP_ENTRY_TTL,
+        max_entry_ttl: DEFAULT_MAX_ENTRY_TTL,
+        ..Default::default()
+    }
+}
+
+/// Id of the native asset contract on [`NETWORK`].
+pub fn native_asset_contract() -> [u8; 32] {
+    let preimage = HashIdPreimage::ContractId(HashIdPreimageContractId {
+        network_id: Hash(hashed_network_id(NETWORK)),
+        contract_id_preimage: ContractIdPreimage::Asset(Asset::Native),
+    });
+
+    Sha256::digest(preimage.to_xdr(Limits::none()).unwrap()).into()
+}
+
+/// Runs `host_function` on [`NETWORK`] at ledger 100, with the all zero
+/// account as source.
+pub fn host_function_params(
+    host_function: HostFunctionParams,
+    network_config: Option<NetworkConfig>,
+) -> SoroflareInvocationParams {
+    SoroflareInvocationParams::new(
+        String::new(),
+        [0; 32],
+        Vec::new(),
+        [0; 32],
+        100,
+        Vec::new(),
+        Some(NETWORK.into()),
+        network_config,
+        None,
+    )
+    .with_host_function(host_function)
+}
+
+/// Deploys the native asset contract on [`NETWORK`] at ledger 100.
+pub fn create_native_asset_contract(network_config: Option<NetworkConfig>) -> SoroflareInvocationParams {
+    host_function_params(HostFunctionParams::CreateAssetContract { asset: Asset::Native }, network_config)
+}
@@ -0,0 +1,197 @@
+//! Minimal WASM binary reader, used to get at the custom sections soroban
+//! contracts carry their spec and metadata in, and upload validation.
+
+use soroban_env_host::{
+    budget::Budget, meta::INTERFACE_VERSION, storage::Storage, xdr::Hash, DiagnosticLevel, Host,
+    LedgerInfo, Vm,
+};
+
+use crate::{interface::EnvMeta, SoroflareError, WasmValidationError, DEFAULT_PROTOCOL_VERSION};
+
+pub const WASM_MAGIC: [u8; 4] = [0x00, 0x61, 0x73, 0x6d];
+const CUSTOM_SECTION_ID: u8 = 0;
+/// Maximum contract size of the public networks, in bytes.
+pub const DEFAULT_MAX_WASM_SIZE: usize = 65_536;
+
+fn invalid(reason: &str) -> SoroflareError {
+    SoroflareError::InvalidParams(format!("invalid wasm: {reason}"))
+}
+
+fn read_leb128_u32(bytes: &[u8], offset: &mut usize) -> Result<u32, SoroflareError> {
+    let mut result: u32 = 0;
+
+    for shift in (0..35).step_by(7) {
+        let byte = *bytes.get(*offset).ok_or_else(|| invalid("truncated integer"))?;
+        *offset += 1;
+        result |= ((byte & 0x7f) as u32) << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+
+    Err(invalid("integer too long"))
+}
+
+/// A section of a WASM module.
+pub struct Section<'a> {
+    pub id: u8,
+    /// Name of a custom section, empty for the other sections.
+    pub name: &'a str,
+    pub payload: &'a [u8],
+}
+
+/// Splits a WASM module into its sections.
+pub fn sections(wasm: &[u8]) -> Result<Vec<Section<'_>>, SoroflareError> {
+    if wasm.len() < 8 || wasm[..4] != WASM_MAGIC {
+        return Err(invalid("missing wasm header"));
+    }
+
+    let mut sections = Vec::new();
+    let mut offset = 8;
+
+    while offset < wasm.len() {
+        let id = wasm[offset];
+        offset += 1;
+
+        let size = read_leb128_u32(wasm, &mut offset)? as usize;
+        let end = offset
+            .checked_add(size)
+            .filter(|end| *end <= wasm.len())
+            .ok_or_else(|| invalid("section out of bounds"))?;
+        let mut contents = &wasm[offset..end];
+        let mut name = "";
+
+        if id == CUSTOM_SECTION_ID {
+            let mut name_offset = 0;
+            let name_len = read_leb128_u32(contents, &mut name_offset)? as usize;
+            let name_bytes = contents
+                .get(name_offset..name_offset + name_len)
+                .ok_or_else(|| invalid("custom section name out of bounds"))?;
+
+            name = std::str::from_utf8(name_bytes).map_err(|_| invalid("custom section name is not utf8"))?;
+            contents = &contents[name_offset + name_len..];
+        }
+
+        sections.push(Section {
+            id,
+            name,
+            payload: contents,
+        });
+        offset = end;
+    }
+
+    Ok(sections)
+}
+
+/// Payload of the custom section `name`, if the module has one.
+pub fn custom_section<'a>(wasm: &'a [u8], name: &str) -> Result<Option<&'a [u8]>, SoroflareError> {
+    Ok(sections(wasm)?
+        .into_iter()
+        .find(|section| section.id == CUSTOM_SECTION_ID && section.name == name)
+        .map(|section| section.payload))
+}
+
+fn check_interface_version(env_meta: EnvMeta) -> Result<(), WasmValidationError> {
+    let supported_protocol_version = (INTERFACE_VERSION >> 32) as u32;
+    let supported_pre_release_version = INTERFACE_VERSION as u32;
+
+    // Same rules as the host: older protocols are always fine, the current one
+    // must either be a release build or match the host's pre-release.
+    let supported = env_meta.protocol_version < supported_protocol_version
+        || (env_meta.protocol_version == supported_protocol_version
+            && (env_meta.pre_release_version == 0
+                || env_meta.pre_release_version == supported_pre_release_version));
+
+    if supported {
+        Ok(())
+    } else {
+        Err(WasmValidationError::UnsupportedInterfaceVersion {
+            protocol_version: env_meta.protocol_version,
+            pre_release_version: env_meta.pre_release_version,
+            supported_protocol_version,
+            supported_pre_release_version,
+        })
+    }
+}
+
+/// Instantiates the module in a host VM, which parses and validates the whole
+/// module with the same WASM features the network enables.
+fn instantiate(wasm: &[u8]) -> Result<(), WasmValidationError> {
+    let rejected = |err| WasmValidationError::Rejected {
+        reason: format!("{err:?}"),
+    };
+
+    // Upload size is already bounded, so metering the parse isn't needed.
+    let budget = Budget::default();
+    budget.reset_unlimited().map_err(rejected)?;
+
+    let host = Host::with_storage_and_budget(Storage::default(), budget);
+    host.set_diagnostic_level(DiagnosticLevel::Debug).map_err(rejected)?;
+    host.set_ledger_info(LedgerInfo {
+        protocol_version: DEFAULT_PROTOCOL_VERSION,
+        ..Default::default()
+    })
+    .map_err(rejected)?;
+
+    Vm::new(&host, Hash([0; 32]), wasm).map(|_| ()).map_err(rejected)
+}
+
+/// Checks that `wasm` is a contract the host can run, returning its env meta.
+pub fn validate(wasm: &[u8], max_size: usize) -> Result<EnvMeta, WasmValidationError> {
+    if wasm.len() > max_size {
+        return Err(WasmValidationError::TooLarge {
+            size: wasm.len(),
+            max_size,
+        });
+    }
+
+    let env_meta = EnvMeta::from_wasm(wasm)
+        .map_err(|err| WasmValidationError::Malformed {
+            reason: match err {
+                SoroflareError::InvalidParams(reason) => reason,
+                other => other.to_string(),
+            },
+        })?
+        .ok_or(WasmValidationError::MissingEnvMeta)?;
+
+    check_interface_version(env_meta)?;
+    instantiate(wasm)?;
+
+    Ok(env_meta)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rejects_oversized_and_malformed_modules() {
+        assert_eq!(
+            validate(&[0; 16], 8),
+            Err(WasmValidationError::TooLarge { size: 16, max_size: 8 })
+        );
+        assert!(matches!(
+            validate(b"not wasm", DEFAULT_MAX_WASM_SIZE),
+            Err(WasmValidationError::Malformed { .. })
+        ));
+        // A valid, empty module without any contract meta.
+        assert_eq!(
+            validate(&[0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00], DEFAULT_MAX_WASM_SIZE),
+            Err(WasmValidationError::MissingEnvMeta)
+        );
+    }
+
+    #[test]
+    fn checks_the_env_interface_version() {
+        let env_meta = |protocol_version, pre_release_version| EnvMeta {
+            interface_version: ((protocol_version as u64) << 32) | pre_release_version as u64,
+            protocol_version,
+            pre_release_version,
+        };
+        let current = (INTERFACE_VERSION >> 32) as u32;
+
+        assert!(check_interface_version(env_meta(current, 0)).is_ok());
+        assert!(check_interface_version(env_meta(current + 1, 0)).is_err());
+    }
+}
@@ -28,14 +28,26 @@ pub async fn main(req: Request, env: Env, ctx: worker::Context) -> Result<Respon
     router = router
         .options("/uploadwasm", |_req, _ctx| Response::empty())
         .post_async("/uploadwasm", routes::snapshot::handle_upload)
+        .options("/uploadsnapshot", |_req, _ctx| Response::empty())
+        .post_async("/uploadsnapshot", routes::snapshot::handle_snapshot_upload)
         .options("/executesnapshot", |_req, _ctx| Response::empty())
-        .post_async("/executesnapshot", routes::snapshot::handle_snapshot);
+        .post_async("/executesnapshot", routes::snapshot::handle_snapshot)
+        .options("/executescenario", |_req, _ctx| Response::empty())
+        .post_async("/executescenario", routes::snapshot::handle_scenario)
+        .options("/contract/:wasm_hash/spec", |_req, _ctx| Response::empty())
+        .get_async("/contract/:wasm_hash/spec", routes::modules::handle_spec)
+        .options("/modules", |_req, _ctx| Response::empty())
+        .get_async("/modules", routes::modules::handle_list)
+        .options("/modules/:wasm_hash", |_req, _ctx| Response::empty())
+        .get_async("/modules/:wasm_hash", routes::modules::handle_get)
+        .head_async("/modules/:wasm_hash", routes::modules::handle_head)
+        .delete_async("/modules/:wasm_hash", routes::modules::handle_delete);
 
     let cors = Cors::new()
         .with_allowed_headers(["*"])
         .with_origins(["*"])
         .with_max_age(86400)
-        .with_methods([Method::Get, Method::Post, Method::Options]);
+        .with_methods([Method::Get, Method::Head, Method::Post, Method::Delete, Method::Options]);
 
     router
         .run(req, env)
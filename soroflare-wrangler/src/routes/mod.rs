@@ -1 +1,2 @@
+pub mod modules;
 pub mod snapshot;
@@ -0,0 +1,248 @@
+use core::{interface::ContractInterface, wasm};
+
+use crate::{
+    response::{BasicJsonResponse, JsonResponse},
+    State,
+};
+use serde::{Deserialize, Serialize};
+
+use worker::{Date, Headers, Request, Response, RouteContext};
+
+const DEFAULT_PAGE_SIZE: u64 = 100;
+/// Largest page the KV list operation returns.
+const MAX_PAGE_SIZE: u64 = 1000;
+
+/// KV metadata recorded with every uploaded module. Modules uploaded before
+/// metadata was recorded have none.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ModuleMetadata {
+    pub size: usize,
+    /// Milliseconds since the unix epoch.
+    pub uploaded_at: u64,
+}
+
+impl ModuleMetadata {
+    pub fn new(module: &[u8]) -> Self {
+        Self {
+            size: module.len(),
+            uploaded_at: Date::now().as_millis(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ModuleListing {
+    hash: String,
+    metadata: Option<ModuleMetadata>,
+}
+
+#[derive(Serialize)]
+struct ModulesPage {
+    modules: Vec<ModuleListing>,
+    /// Pass as `cursor` to get the next page, absent on the last page.
+    cursor: Option<String>,
+}
+
+/// Validates the `wasm_hash` route parameter, returning it in lowercase hex.
+fn hash_param(ctx: &RouteContext<State>) -> Result<String, Result<Response, worker::Error>> {
+    let hash = ctx.param("wasm_hash").map(|hash| hash.to_lowercase()).unwrap_or_default();
+
+    match hex::decode(&hash) {
+        Ok(bytes) if bytes.len() == 32 => Ok(hash),
+        _ => Err(JsonResponse::new("Invalid wasm hash, expected 32 hex encoded bytes", 400)
+            .with_opt(hash)
+            .into()),
+    }
+}
+
+/// Reads a module stored by `handle_upload`, `None` if it wasn't uploaded.
+///
+/// Modules uploaded before they were stored as raw bytes are hex encoded,
+/// those are rewritten as raw bytes the first time they're read.
+pub async fn stored_module(
+    hash: &str,
+    ctx: &RouteContext<State>,
+) -> Result<Option<Vec<u8>>, Result<Response, worker::Error>> {
+    let modules = ctx.kv("MODULES").unwrap();
+
+    let stored = match modules.get(hash).bytes().await {
+        Ok(Some(stored)) => stored,
+        Ok(None) => return Ok(None),
+        Err(_) => {
+            return Err(JsonResponse::new("Internal error when executing KV query", 500)
+                .with_opt(hash.to_string())
+                .into())
+        }
+    };
+
+    if stored.starts_with(&wasm::WASM_MAGIC) {
+        return Ok(Some(stored));
+    }
+
+    let Some(module) = hex::decode(&stored)
+        .ok()
+        .filter(|module| module.starts_with(&wasm::WASM_MAGIC))
+    else {
+        return Err(JsonResponse::new("Stored module is corrupted", 500)
+            .with_opt(hash.to_string())
+            .into());
+    };
+
+    // Best effort, a failed migration just decodes the hex again next time.
+    if let Ok(put) = modules.put_bytes(hash, &module) {
+        let _ = put.execute().await;
+    }
+
+    Ok(Some(module))
+}
+
+pub async fn handle_spec(_req: Request, ctx: RouteContext<State>) -> Result<Response, worker::Error> {
+    let hash = match hash_param(&ctx) {
+        Ok(hash) => hash,
+        Err(err) => return err,
+    };
+    let module = match stored_module(&hash, &ctx).await {
+        Ok(Some(module)) => module,
+        Ok(None) => {
+            return JsonResponse::new("Module was not uploaded to soroflare", 404)
+                .with_opt(hash)
+                .into()
+        }
+        Err(err) => return err,
+    };
+
+    match ContractInterface::from_wasm(&module) {
+        Ok(interface) => JsonResponse::new("Successfully decoded contract spec", 200)
+            .with_opt(interface)
+            .into(),
+        Err(err) => BasicJsonResponse::new(err.to_string(), 422).into(),
+    }
+}
+
+/// `GET /modules?limit=&cursor=`, lists the stored module hashes.
+pub async fn handle_list(req: Request, ctx: RouteContext<State>) -> Result<Response, worker::Error> {
+    let mut limit = DEFAULT_PAGE_SIZE;
+    let mut cursor = None;
+
+    if let Ok(url) = req.url() {
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "limit" => match value.parse::<u64>() {
+                    Ok(value) if (1..=MAX_PAGE_SIZE).contains(&value) => limit = value,
+                    _ => {
+                        return JsonResponse::new("Invalid limit", 400)
+                            .with_opt(format!("expected a number between 1 and {MAX_PAGE_SIZE}"))
+                            .into()
+                    }
+                },
+                "cursor" => cursor = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+    }
+
+    let modules = ctx.kv("MODULES").unwrap();
+    let mut list = modules.list().limit(limit);
+    if let Some(cursor) = cursor {
+        list = list.cursor(cursor);
+    }
+
+    let Ok(page) = list.execute().await else {
+        return BasicJsonResponse::new("Internal error when executing KV query", 500).into();
+    };
+
+    let modules = page
+        .keys
+        .into_iter()
+        .map(|key| ModuleListing {
+            hash: key.name,
+            metadata: key.metadata.and_then(|metadata| serde_json::from_value(metadata).ok()),
+        })
+        .collect();
+
+    JsonResponse::new("Successfully listed modules", 200)
+        .with_opt(ModulesPage {
+            modules,
+            cursor: page.cursor.filter(|_| !page.list_complete),
+        })
+        .into()
+}
+
+/// `GET /modules/{wasm_hash}`, the raw WASM of a stored module.
+pub async fn handle_get(_req: Request, ctx: RouteContext<State>) -> Result<Response, worker::Error> {
+    let hash = match hash_param(&ctx) {
+        Ok(hash) => hash,
+        Err(err) => return err,
+    };
+
+    match stored_module(&hash, &ctx).await {
+        Ok(Some(module)) => {
+            let mut headers = Headers::new();
+            headers.set("Content-Type", "application/wasm")?;
+
+            Ok(Response::from_bytes(module)?.with_headers(headers))
+        }
+        Ok(None) => JsonResponse::new("Module was not uploaded to soroflare", 404)
+            .with_opt(hash)
+            .into(),
+        Err(err) => err,
+    }
+}
+
+/// `HEAD /modules/{wasm_hash}`, 200 if the module is stored and 404 if not.
+pub async fn handle_head(_req: Request, ctx: RouteContext<State>) -> Result<Response, worker::Error> {
+    let Ok(hash) = hash_param(&ctx) else {
+        return Ok(Response::empty()?.with_status(400));
+    };
+
+    let modules = ctx.kv("MODULES").unwrap();
+    // Listing by the full hash finds the key without downloading the module.
+    match modules.list().prefix(hash.clone()).limit(1).execute().await {
+        Ok(page) if page.keys.iter().any(|key| key.name == hash) => Response::empty(),
+        Ok(_) => Ok(Response::empty()?.with_status(404)),
+        Err(_) => Ok(Response::empty()?.with_status(500)),
+    }
+}
+
+/// Compares without short-circuiting, so the comparison time doesn't leak how
+/// much of the token matched.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// `DELETE /modules/{wasm_hash}`, authenticated with the `MODULES_ADMIN_TOKEN`
+/// secret as bearer token. Deleting is disabled when the secret isn't set.
+pub async fn handle_delete(req: Request, ctx: RouteContext<State>) -> Result<Response, worker::Error> {
+    let Ok(admin_token) = ctx.secret("MODULES_ADMIN_TOKEN") else {
+        return BasicJsonResponse::new("Deleting modules is disabled on this instance", 403).into();
+    };
+
+    let authorized = req
+        .headers()
+        .get("Authorization")?
+        .and_then(|header| header.strip_prefix("Bearer ").map(str::to_owned))
+        .is_some_and(|token| tokens_match(&token, &admin_token.to_string()));
+    if !authorized {
+        return BasicJsonResponse::new("Missing or invalid admin token", 401).into();
+    }
+
+    let hash = match hash_param(&ctx) {
+        Ok(hash) => hash,
+        Err(err) => return err,
+    };
+
+    let modules = ctx.kv("MODULES").unwrap();
+    match modules.delete(&hash).await {
+        Ok(()) => JsonResponse::new("Successfully deleted module", 200)
+            .with_opt(hash)
+            .into(),
+        Err(_) => JsonResponse::new("Internal error when executing KV query", 500)
+            .with_opt(hash)
+            .into(),
+    }
+}
@@ -1,19 +1,23 @@
-use core::{SoroflareInvocation, SoroflareInvocationParams};
+use core::{
+    native,
+    snapshot::{LedgerSnapshot, SnapshotFile},
+    wasm, BudgetLimits, BudgetUsage, CostBreakdown, DecodedEvent, EventFilter, EventKind, PrngSeed, SimulationResult, SoroflareError, SoroflareInvocation, SoroflareInvocationParams, SoroflareScenario,
+};
 
 use crate::{
     response::{BasicJsonResponse, JsonResponse},
+    routes::modules::{stored_module, ModuleMetadata},
     State,
 };
+use base64::{engine::general_purpose::STANDARD, Engine};
 use serde::Serialize;
 use sha2::{Digest, Sha256};
 
 
-use soroban_env_host::xdr::{BytesM, ContractCodeEntry, ContractExecutable, ExtensionPoint, Hash, LedgerEntry, LedgerEntryData, LedgerEntryExt, LedgerKey, LedgerKeyContractCode, ScVal};
-use soroban_simulation::simulation::InvokeHostFunctionSimulationResult;
+use soroban_env_host::xdr::{BytesM, ContractCodeEntry, ContractExecutable, ExtensionPoint, Hash, LedgerEntry, LedgerEntryData, LedgerEntryExt, LedgerKey, LedgerKeyContractCode, Limits, ScVal, SorobanTransactionData, WriteXdr};
 
 use worker::{kv::KvStore, Request, Response, RouteContext};
 
-// TODO: wait on clarification about the preamble.
 /// Instructions for the client to restore any potentially expired
 /// ledger entries
 #[derive(Serialize, Default)]
@@ -22,22 +26,262 @@ pub struct RestorePreamble {
     transaction_data: String,
 }
 
+impl RestorePreamble {
+    fn new(transaction_data: &SorobanTransactionData) -> Result<Self, SoroflareError> {
+        Ok(Self {
+            min_resource_fee: transaction_data.resource_fee.to_string(),
+            transaction_data: xdr_base64(transaction_data)?,
+        })
+    }
+}
+
+fn xdr_base64<T: WriteXdr>(value: &T) -> Result<String, SoroflareError> {
+    let xdr = value
+        .to_xdr(Limits::none())
+        .map_err(|e| SoroflareError::Simulation(e.to_string()))?;
+
+    Ok(STANDARD.encode(xdr))
+}
+
+/// Response options, given as query parameters.
+#[derive(Default, Clone)]
+pub struct ExecutionOptions {
+    /// Return the full ledger state after the invocation (`return_snapshot=true`).
+    return_snapshot: bool,
+    /// Return the diagnostic events stream (`diagnostic_events=true`).
+    diagnostic_events: bool,
+    /// Also render the return value as native JSON (`native=true`).
+    native: bool,
+    /// Return CPU and memory per cost type (`cost_breakdown=true`).
+    cost_breakdown: bool,
+    /// Filter for the decoded events, built from the repeatable `event_type`
+    /// and `event_contract` parameters.
+    event_filter: EventFilter,
+}
+
+impl ExecutionOptions {
+    fn from_request(req: &Request) -> Result<Self, Result<Response, worker::Error>> {
+        let mut options = Self::default();
+
+        if let Ok(url) = req.url() {
+            for (key, value) in url.query_pairs() {
+                match key.as_ref() {
+                    "return_snapshot" => options.return_snapshot = value == "true",
+                    "diagnostic_events" => options.diagnostic_events = value == "true",
+                    "native" => options.native = value == "true",
+                    "cost_breakdown" => options.cost_breakdown = value == "true",
+                    "event_type" => options.event_filter.kinds.push(match value.as_ref() {
+                        "contract" => EventKind::Contract,
+                        "system" => EventKind::System,
+                        "diagnostic" => EventKind::Diagnostic,
+                        _ => {
+                            return Err(JsonResponse::new("Invalid event_type", 400)
+                                .with_opt(format!("{value:?}, expected contract, system or diagnostic"))
+                                .into())
+                        }
+                    }),
+                    "event_contract" => options.event_filter.contracts.push(value.into_owned()),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+#[derive(Serialize)]
+pub struct EventsResponse {
+    contract: Vec<DecodedEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diagnostic: Option<Vec<DecodedEvent>>,
+}
+
+#[derive(Serialize)]
+pub struct ExecutionResponse {
+    #[serde(flatten)]
+    simulation: SimulationResult,
+    contract: Option<String>,
+    source_account: String,
+    /// Contract id or WASM hash created by a deployment host function.
+    created: Option<String>,
+    restore_preamble: Option<RestorePreamble>,
+    archived_entries: Vec<LedgerKey>,
+    /// Hex encoded PRNG seed the invocation ran with.
+    prng_seed: String,
+    /// Decoded events, matching the request's event filter.
+    events: EventsResponse,
+    /// The return value rendered as native JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    native_result: Option<serde_json::Value>,
+    /// Ledger state after the invocation, in the shape of the params'
+    /// `ledger_entries`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshot: Option<Vec<(LedgerKey, (LedgerEntry, Option<u32>))>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    costs: Option<CostBreakdown>,
+    /// Base64 `SorobanTransactionData` to attach to the transaction as is.
+    transaction_data_xdr: Option<String>,
+    /// Base64 `SorobanAuthorizationEntry`s for the operation's `auth`.
+    auth_xdr: Vec<String>,
+}
+
+impl ExecutionResponse {
+    fn new(
+        invocation: &SoroflareInvocation,
+        simulation: SimulationResult,
+        options: &ExecutionOptions,
+    ) -> Result<Self, SoroflareError> {
+        let restore_preamble = match invocation.restore_preamble()? {
+            Some(restore) => Some(RestorePreamble::new(&restore.transaction_data)?),
+            None => None,
+        };
+
+        let snapshot = options
+            .return_snapshot
+            .then(|| invocation.snapshot_after(&simulation).entries());
+
+        let native_result = options
+            .native
+            .then(|| native::to_native(&simulation.invoke_result));
+
+        let costs = if options.cost_breakdown {
+            Some(invocation.cost_breakdown(&simulation)?)
+        } else {
+            None
+        };
+
+        let transaction_data_xdr = simulation.transaction_data.as_ref().map(xdr_base64).transpose()?;
+        let auth_xdr = simulation.auth.iter().map(xdr_base64).collect::<Result<_, _>>()?;
+
+        let events = EventsResponse {
+            contract: options.event_filter.decode(&simulation.contract_events),
+            diagnostic: options
+                .diagnostic_events
+                .then(|| options.event_filter.decode(&simulation.diagnostic_events)),
+        };
+
+        Ok(Self {
+            contract: invocation.contract(),
+            source_account: invocation.source_account(),
+            created: invocation.created_id(&simulation),
+            simulation,
+            restore_preamble,
+            archived_entries: invocation.archived_entries(),
+            prng_seed: hex::encode(invocation.prng_seed()),
+            events,
+            native_result,
+            snapshot,
+            costs,
+            transaction_data_xdr,
+            auth_xdr,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct ScenarioResponse {
+    steps: Vec<ExecutionResponse>,
+    /// Ledger state after the last step, in the shape of the params'
+    /// `ledger_entries`.
+    ledger_entries: Vec<(LedgerKey, (LedgerEntry, Option<u32>))>,
+}
+
+#[derive(Serialize)]
+pub struct BudgetExceededResponse {
+    error: String,
+    /// CPU and memory consumed before the budget ran out, out of the limits.
+    usage: Option<BudgetUsage>,
+}
+
+/// Server-wide budget ceiling, from the `SOROBAN_CPU_BUDGET` and
+/// `SOROBAN_MEMORY_BUDGET` variables.
+fn budget_ceiling(ctx: &RouteContext<State>) -> BudgetLimits {
+    let limit = |name| ctx.var(name).ok().and_then(|limit| limit.to_string().parse().ok());
+
+    BudgetLimits {
+        cpu_insns: limit("SOROBAN_CPU_BUDGET"),
+        mem_bytes: limit("SOROBAN_MEMORY_BUDGET"),
+    }
+}
+
+/// HTTP status and message for a soroflare error.
+fn error_status(err: &SoroflareError) -> (u16, &'static str) {
+    match err {
+        SoroflareError::InvalidParams(_) => (400, "Invalid invocation parameters"),
+        SoroflareError::MissingLedgerEntry(_) => (404, "Ledger entry missing from snapshot"),
+        SoroflareError::BudgetExceeded { .. } => (422, "Budget exceeded during execution"),
+        SoroflareError::Host(_) => (422, "Host error during execution"),
+        SoroflareError::Simulation(_) => (500, "Internal error when simulating invocation"),
+    }
+}
+
+fn error_response_with_message(
+    message: String,
+    err: SoroflareError,
+) -> Result<Response, worker::Error> {
+    let (status, _) = error_status(&err);
+
+    match err {
+        SoroflareError::InvalidParams(reason) | SoroflareError::Simulation(reason) => {
+            JsonResponse::new(message, status).with_opt(reason).into()
+        }
+        SoroflareError::MissingLedgerEntry(key) => {
+            JsonResponse::new(message, status).with_opt(key).into()
+        }
+        SoroflareError::BudgetExceeded { error, usage } => JsonResponse::new(message, status)
+            .with_opt(BudgetExceededResponse {
+                error: format!("{error:?}"),
+                usage,
+            })
+            .into(),
+        SoroflareError::Host(err) => {
+            JsonResponse::new(message, status)
+                .with_opt(format!("{err:?}"))
+                .into()
+        }
+    }
+}
+
+/// Maps a soroflare error to the JSON response returned to the client.
+fn error_response(err: SoroflareError) -> Result<Response, worker::Error> {
+    let (_, message) = error_status(&err);
+    error_response_with_message(message.to_string(), err)
+}
+
 pub struct Generic;
 
 impl Generic {
-    async fn run_with_snapshot(
-        req: &mut Request,
-        modules: KvStore,
-    ) -> Result<InvokeHostFunctionSimulationResult, Result<Response, worker::Error>> {
-        let mut params: SoroflareInvocationParams = req.json().await.unwrap();
+    /// Fills in the server-side defaults of the params and adds the binaries
+    /// uploaded to soroflare that the invocation needs.
+    async fn prepare_params(
+        params: &mut SoroflareInvocationParams,
+        ctx: &RouteContext<State>,
+    ) -> Result<(), Result<Response, worker::Error>> {
+        if params.prng_seed().is_none() {
+            if let Some(seed) = default_prng_seed(ctx)? {
+                params.set_prng_seed(seed);
+            }
+        }
+
+        params.cap_budget_limits(budget_ceiling(ctx));
+
+        if let Some(snapshot_id) = params.snapshot_id().map(str::to_owned) {
+            let snapshot = Self::stored_snapshot(&snapshot_id, ctx).await?;
+            params
+                .resolve_snapshot_id(snapshot)
+                .map_err(error_response)?;
+        }
         
         // Here soroflare automatically adds the binaries requested if needed
         let new_entries = {
             let mut new_entries = params.entries();
-            let mut wasm_hashes = Vec::new();
-            let keys: Vec<LedgerKey> = params.entries().iter().map(|e| e.0.clone()).collect();
+            let mut wasm_hashes: Vec<[u8; 32]> = params.host_function_wasm_hash().into_iter().collect();
+            // Also covers the entries of a snapshot file given in the params.
+            let all_entries = params.snapshot().entries();
+            let keys: Vec<LedgerKey> = all_entries.iter().map(|e| e.0.clone()).collect();
 
-            for val in &new_entries {
+            for val in &all_entries {
                 if let LedgerEntryData::ContractData(contract_data) = &val.1.0.data {
                     if let ScVal::ContractInstance(instance) = &contract_data.val {
                         if let ContractExecutable::Wasm(hash) = &instance.executable {
@@ -56,40 +300,32 @@ impl Generic {
                     }
                 }) {
                     let hex_hash = hex::encode(hash);
-                    if let Ok(module) = modules.get(&hex_hash).text().await {
-                        if let Some(module) = module {
-                            let module = hex::decode(module).unwrap();
-
-                            let key =
-                                LedgerKey::ContractCode(LedgerKeyContractCode { hash: Hash(hash) });
-                            let val = (
-                                LedgerEntry {
-                                    last_modified_ledger_seq: 0,
-                                    data: LedgerEntryData::ContractCode(ContractCodeEntry {
-                                        ext: ExtensionPoint::V0,
-                                        hash: Hash(hash),
-                                        code: BytesM::try_from(module).unwrap(),
-                                    }),
-                                    ext: LedgerEntryExt::V0,
-                                },
-                                Some(u32::MAX)
-                            );
-
-                            new_entries.push((key, val));
-                        } else {
-                            return Err(
-                                JsonResponse::new("Wasm was not installed on soroflare", 400)
-                                    .with_opt(hex_hash)
-                                    .into(),
-                            );
-                        }
-                    } else {
-                        return Err(
-                            JsonResponse::new("Internal error when executing KV query", 400)
-                                .with_opt(hex_hash)
-                                .into(),
-                        );
+                    let Some(module) = stored_module(&hex_hash, ctx).await? else {
+                        return Err(JsonResponse::new("Wasm was not installed on soroflare", 400)
+                            .with_opt(hex_hash)
+                            .into());
+                    };
+                    let Ok(code) = BytesM::try_from(module) else {
+                        return Err(JsonResponse::new("Stored module is too large", 500)
+                            .with_opt(hex_hash)
+                            .into());
                     };
+
+                    let key = LedgerKey::ContractCode(LedgerKeyContractCode { hash: Hash(hash) });
+                    let val = (
+                        LedgerEntry {
+                            last_modified_ledger_seq: 0,
+                            data: LedgerEntryData::ContractCode(ContractCodeEntry {
+                                ext: ExtensionPoint::V0,
+                                hash: Hash(hash),
+                                code,
+                            }),
+                            ext: LedgerEntryExt::V0,
+                        },
+                        Some(u32::MAX)
+                    );
+
+                    new_entries.push((key, val));
                 }
             }
 
@@ -97,9 +333,84 @@ impl Generic {
         };
         params.set_entries(new_entries);
 
-        let soroflare_simulator = SoroflareInvocation::new(params);
+        Ok(())
+    }
+
+    async fn stored_snapshot(
+        snapshot_id: &str,
+        ctx: &RouteContext<State>,
+    ) -> Result<LedgerSnapshot, Result<Response, worker::Error>> {
+        let snapshots = snapshots_kv(ctx)?;
+
+        match snapshots.get(snapshot_id).text().await {
+            Ok(Some(snapshot)) => serde_json::from_str(&snapshot).map_err(|_| {
+                JsonResponse::new("Stored snapshot is corrupted", 500)
+                    .with_opt(snapshot_id.to_string())
+                    .into()
+            }),
+            Ok(None) => Err(JsonResponse::new("Snapshot was not uploaded to soroflare", 400)
+                .with_opt(snapshot_id.to_string())
+                .into()),
+            Err(_) => Err(JsonResponse::new("Internal error when executing KV query", 500)
+                .with_opt(snapshot_id.to_string())
+                .into()),
+        }
+    }
+
+    async fn run_with_snapshot(
+        req: &mut Request,
+        ctx: &RouteContext<State>,
+    ) -> Result<ExecutionResponse, Result<Response, worker::Error>> {
+        let options = ExecutionOptions::from_request(req)?;
+        let mut params: SoroflareInvocationParams = match req.json().await {
+            Ok(params) => params,
+            Err(err) => return Err(error_response(SoroflareError::InvalidParams(err.to_string()))),
+        };
+
+        Self::prepare_params(&mut params, ctx).await?;
+
+        let soroflare_simulator = SoroflareInvocation::new(params).map_err(error_response)?;
         
-        Ok(soroflare_simulator.resolve())
+        let simulation = soroflare_simulator.resolve().map_err(error_response)?;
+
+        ExecutionResponse::new(&soroflare_simulator, simulation, &options).map_err(error_response)
+    }
+
+    async fn run_scenario(
+        req: &mut Request,
+        ctx: &RouteContext<State>,
+    ) -> Result<ScenarioResponse, Result<Response, worker::Error>> {
+        let options = ExecutionOptions::from_request(req)?;
+        let mut scenario: SoroflareScenario = match req.json().await {
+            Ok(scenario) => scenario,
+            Err(err) => return Err(error_response(SoroflareError::InvalidParams(err.to_string()))),
+        };
+
+        for params in scenario.steps_mut() {
+            Self::prepare_params(params, ctx).await?;
+        }
+
+        let step_error = |idx: usize, err: SoroflareError| {
+            let message = format!("Step {idx} failed: {}", error_status(&err).1);
+            error_response_with_message(message, err)
+        };
+
+        let (steps, state) = scenario
+            .resolve()
+            .map_err(|(idx, err)| step_error(idx, err))?;
+
+        let mut responses = Vec::with_capacity(steps.len());
+        for (idx, step) in steps.into_iter().enumerate() {
+            responses.push(
+                ExecutionResponse::new(&step.invocation, step.result, &options)
+                    .map_err(|err| step_error(idx, err))?,
+            );
+        }
+
+        Ok(ScenarioResponse {
+            steps: responses,
+            ledger_entries: state.entries(),
+        })
     }
 }
 
@@ -113,36 +424,104 @@ pub async fn handle_upload(
         return BasicJsonResponse::new("Error reading submitted data in body", 400).into();
     };
 
-    // validate WASM magic word
-    if data.len() <= 4
-        || !(data[0] == 0x00 && data[1] == 0x61 && data[2] == 0x73 && data[3] == 0x6d)
-    {
-        return BasicJsonResponse::new("Submitted data does not contain valid WASM code", 400)
+    if let Err(err) = wasm::validate(&data, wasm::DEFAULT_MAX_WASM_SIZE) {
+        return JsonResponse::new("Submitted data does not contain valid WASM code", 400)
+            .with_opt(err)
             .into();
     };
 
     let modules = ctx.kv("MODULES").unwrap();
     let hash: [u8; 32] = Sha256::digest(data.as_slice()).into();
 
-    let _ = modules
-        .put(&hex::encode(hash), hex::encode(data))
-        .unwrap()
-        .execute()
-        .await
-        .unwrap();
+    let stored = match modules
+        .put_bytes(&hex::encode(hash), &data)
+        .and_then(|put| put.metadata(ModuleMetadata::new(&data)))
+    {
+        Ok(put) => put.execute().await.is_ok(),
+        Err(_) => false,
+    };
+    if !stored {
+        return JsonResponse::new("Internal error when storing the module", 500)
+            .with_opt(hex::encode(hash))
+            .into();
+    }
 
     JsonResponse::new("Successfully uploaded wasm", 200)
         .with_opt(hex::encode(hash))
         .into()
 }
 
-pub async fn handle_snapshot(
+pub async fn handle_snapshot_upload(
     mut req: Request,
     ctx: RouteContext<State>,
 ) -> Result<Response, worker::Error> {
-    let modules = ctx.kv("MODULES").unwrap();
+    let snapshot: SnapshotFile = if let Ok(snapshot) = req.json().await {
+        snapshot
+    } else {
+        return BasicJsonResponse::new("Submitted data does not contain a valid snapshot", 400)
+            .into();
+    };
+
+    // Stored in its canonical form, so that the same state always maps to the
+    // same id.
+    let snapshot = serde_json::to_string(&snapshot.into_snapshot())?;
+    let hash: [u8; 32] = Sha256::digest(snapshot.as_bytes()).into();
 
-    let result = Generic::run_with_snapshot(&mut req, modules).await;
+    let snapshots = match snapshots_kv(&ctx) {
+        Ok(snapshots) => snapshots,
+        Err(err) => return err,
+    };
+    let stored = match snapshots.put(&hex::encode(hash), snapshot) {
+        Ok(put) => put.execute().await.is_ok(),
+        Err(_) => false,
+    };
+    if !stored {
+        return JsonResponse::new("Internal error when storing the snapshot", 500)
+            .with_opt(hex::encode(hash))
+            .into();
+    }
+
+    JsonResponse::new("Successfully uploaded snapshot", 200)
+        .with_opt(hex::encode(hash))
+        .into()
+}
+
+/// The `SNAPSHOTS` namespace. Deployments without the binding can't store
+/// snapshots, which is a server configuration error.
+fn snapshots_kv(ctx: &RouteContext<State>) -> Result<KvStore, Result<Response, worker::Error>> {
+    ctx.kv("SNAPSHOTS").map_err(|_| {
+        BasicJsonResponse::new(
+            "Snapshot storage is not configured on this server, missing the SNAPSHOTS KV namespace",
+            500,
+        )
+        .into()
+    })
+}
+
+/// The server's default seed, from the `PRNG_SEED` variable. A malformed
+/// value is a server configuration error, not a problem with the request.
+fn default_prng_seed(ctx: &RouteContext<State>) -> Result<Option<PrngSeed>, Result<Response, worker::Error>> {
+    let Ok(seed) = ctx.var("PRNG_SEED") else {
+        return Ok(None);
+    };
+
+    let seed = PrngSeed::Hex(seed.to_string());
+    if seed.to_bytes().is_err() {
+        return Err(BasicJsonResponse::new(
+            "Invalid PRNG_SEED in the server configuration, expected 32 hex encoded bytes",
+            500,
+        )
+        .into());
+    }
+
+    Ok(Some(seed))
+}
+
+pub async fn handle_snapshot(
+    mut req: Request,
+    ctx: RouteContext<State>,
+) -> Result<Response, worker::Error> {
+    let result = Generic::run_with_snapshot(&mut req, &ctx).await;
 
     if let Err(err) = result {
         return err;
@@ -157,6 +536,18 @@ pub async fn handle_snapshot(
     }
 }
 
+pub async fn handle_scenario(
+    mut req: Request,
+    ctx: RouteContext<State>,
+) -> Result<Response, worker::Error> {
+    match Generic::run_scenario(&mut req, &ctx).await {
+        Ok(scenario) => JsonResponse::new("Successful execution", 200)
+            .with_opt(scenario)
+            .into(),
+        Err(err) => err,
+    }
+}
+
 mod test {
     use soroban_env_host::xdr::{
         AccountEntry, AccountEntryExt, AccountId, Int128Parts, LedgerKeyAccount, PublicKey,
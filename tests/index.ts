@@ -27,42 +27,9 @@ const json = {
             "multiplicative_factor": 1.0
         }
     },
-    "contract": [
-        98,
-        128,
-        3,
-        251,
-        171,
-        144,
-        167,
-        231,
-        12,
-        233,
-        182,
-        179,
-        60,
-        187,
-        105,
-        132,
-        91,
-        174,
-        59,
-        69,
-        181,
-        166,
-        40,
-        152,
-        72,
-        3,
-        184,
-        5,
-        204,
-        157,
-        39,
-        17
-    ],
+    "contract": "CBRIAA73VOIKPZYM5G3LGPF3NGCFXLR3IW22MKEYJAB3QBOMTUTRCASK",
     "fname": "add_with",
-    "ledger_entries": snapshot.ledger.ledger_entries,
+    "snapshot": snapshot,
     "ledger_sequence": 0,
     "network": "Test SDF Network ; September 2015",
     "network_config": null,
@@ -77,40 +44,7 @@ const json = {
             "u32": 95
         }
     ],
-    "source_account": [
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0
-    ]
+    "source_account": "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF"
 }
 
 fetch('http://localhost:8787/executesnapshot', {